@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use mupdf::{Colorspace, Matrix, Page};
+
+use crate::passwords::Passwords;
+use crate::pdf;
+
+/// Resolution pages are rendered at to measure them, low since only the amount of ink matters
+const RENDER_DPI: f32 = 24.0;
+/// Gray levels below this count as ink, so paper texture and scanner noise do not
+const INK_LEVEL: u8 = 200;
+
+/// How blank pages are recognised
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct BlankOptions {
+    /// Largest share of the page, from 0 to 1, that can be covered in ink for it to count as blank
+    pub threshold: f32,
+}
+
+impl Default for BlankOptions {
+    fn default() -> Self {
+        return Self { threshold: 0.001 };
+    }
+}
+
+/// Blank pages found in one document
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct BlankPages {
+    pub path: String,
+    /// 0-based page indices
+    pub pages: Vec<i32>,
+}
+
+/// Renders the page in grayscale and returns the share of it covered in ink, from 0 to 1
+///
+/// # Errors
+///
+/// Returns an error when the page can not be rendered.
+pub fn ink_coverage(page: &Page) -> Result<f32, String> {
+    let scale = RENDER_DPI / 72.0;
+    let pixmap = page
+        .to_pixmap(
+            &Matrix::new_scale(scale, scale),
+            &Colorspace::device_gray(),
+            false,
+            false,
+        )
+        .map_err(|e| return e.to_string())?;
+
+    let samples = pixmap.samples();
+    if samples.is_empty() {
+        return Ok(0.0);
+    }
+
+    let inked = samples
+        .iter()
+        .filter(|&&level| return level < INK_LEVEL)
+        .count();
+
+    #[allow(clippy::cast_precision_loss)]
+    return Ok(inked as f32 / samples.len() as f32);
+}
+
+/// Whether the ink on the page is at or below the blank threshold
+///
+/// # Errors
+///
+/// Returns an error when the page can not be rendered.
+pub fn is_blank(page: &Page, options: BlankOptions) -> Result<bool, String> {
+    return Ok(ink_coverage(page)? <= options.threshold);
+}
+
+/// 0-based pages of a PDF or image document that are blank
+///
+/// # Errors
+///
+/// Returns an error when the document can not be opened or one of its pages rendered.
+pub fn find_blank_pages(
+    path: &Path,
+    options: BlankOptions,
+    passwords: &Passwords,
+) -> Result<Vec<i32>, String> {
+    let document = pdf::open_document(path, passwords)?;
+    let page_count = document.page_count().map_err(|e| return e.to_string())?;
+
+    let mut blank_pages = vec![];
+    for index in 0..page_count {
+        let page = document
+            .load_page(index)
+            .map_err(|e| return e.to_string())?;
+        if is_blank(&page, options)? {
+            blank_pages.push(index);
+        }
+    }
+
+    return Ok(blank_pages);
+}
@@ -0,0 +1,132 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use file_id::FileId;
+use log::warn;
+use xxhash_rust::xxh3::xxh3_64_with_seed;
+
+/// Ids are sent to the frontend as JSON numbers, so keep them within the
+/// range a javascript number can represent exactly.
+const ID_MASK: u64 = (1 << 53) - 1;
+
+/// Never handed out to a path, so it can stand for entries that are not files
+pub const RESERVED_ID: u64 = 0;
+
+/// Hands out entry ids that are stable across restarts and, where the platform
+/// exposes a file identity (inode or file index), across renames.
+#[derive(Default)]
+pub struct EntryIds {
+    by_id: HashMap<u64, String>,
+    by_key: HashMap<String, u64>,
+    /// Ids of the keys involved in a collision. Which key had to be rehashed depends on the
+    /// order they were seen in, so these are saved and restored rather than worked out again.
+    pinned: BTreeMap<String, u64>,
+    pinned_changed: bool,
+    /// Put in front of the identity keys of paths, see [`EntryIds::in_namespace`]
+    namespace: String,
+}
+
+/// Builds the identity key for a path, preferring the file identity over the path itself
+fn identity_key(path: &Path) -> String {
+    return match file_id::get_file_id(path) {
+        Ok(FileId::Inode {
+            device_id,
+            inode_number,
+        }) => format!("inode:{device_id}:{inode_number}"),
+        Ok(FileId::LowRes {
+            volume_serial_number,
+            file_index,
+        }) => format!("file:{volume_serial_number}:{file_index}"),
+        Ok(FileId::HighRes {
+            volume_serial_number,
+            file_id,
+        }) => format!("file:{volume_serial_number}:{file_id}"),
+        Err(_) => format!("path:{}", path.to_string_lossy()),
+    };
+}
+
+impl EntryIds {
+    /// Starts from ids pinned by an earlier session, see [`EntryIds::pinned`]
+    #[must_use]
+    pub fn with_pinned(pinned: BTreeMap<String, u64>) -> Self {
+        let mut ids = Self::default();
+        for (key, id) in &pinned {
+            ids.by_id.insert(*id, key.clone());
+            ids.by_key.insert(key.clone(), *id);
+        }
+        ids.pinned = pinned;
+
+        return ids;
+    }
+
+    /// Ids that have to be kept for the same keys to get the same ids after a restart
+    #[must_use]
+    pub const fn pinned(&self) -> &BTreeMap<String, u64> {
+        return &self.pinned;
+    }
+
+    /// Whether ids were pinned since this was last called, so they need saving
+    pub fn take_pinned_changed(&mut self) -> bool {
+        return std::mem::take(&mut self.pinned_changed);
+    }
+
+    pub fn id_for(&mut self, path: &Path) -> u64 {
+        let key = format!("{}{}", self.namespace, identity_key(path));
+        return self.id_for_key(&key);
+    }
+
+    /// Runs `assign` with paths given ids of their own, so a file listed in two places, such
+    /// as a workspace and the loose files, gets a different id in each
+    pub fn in_namespace<T, F>(&mut self, namespace: &str, assign: F) -> T
+    where
+        F: FnOnce(&mut Self) -> T,
+    {
+        let outer = std::mem::replace(&mut self.namespace, format!("{namespace}:"));
+        let result = assign(self);
+        self.namespace = outer;
+
+        return result;
+    }
+
+    /// Id of an identity key, as built for a path by [`EntryIds::id_for`]
+    pub fn id_for_key(&mut self, key: &str) -> u64 {
+        if let Some(id) = self.by_key.get(key) {
+            return *id;
+        }
+
+        let mut seed: u64 = 0;
+        let id = loop {
+            let candidate = xxh3_64_with_seed(key.as_bytes(), seed) & ID_MASK;
+
+            if candidate == RESERVED_ID {
+                warn!(
+                    "Entry id of {key} is reserved, rehashing with seed {}",
+                    seed + 1
+                );
+                seed += 1;
+                continue;
+            }
+
+            match self.by_id.get(&candidate) {
+                Some(existing) if existing != key => {
+                    warn!(
+                        "Entry id collision between {existing} and {key}, rehashing with seed {}",
+                        seed + 1
+                    );
+                    self.pinned.insert(existing.clone(), candidate);
+                    seed += 1;
+                }
+                _ => break candidate,
+            }
+        };
+
+        if seed > 0 {
+            self.pinned.insert(key.to_string(), id);
+            self.pinned_changed = true;
+        }
+        self.by_id.insert(id, key.to_string());
+        self.by_key.insert(key.to_string(), id);
+
+        return id;
+    }
+}
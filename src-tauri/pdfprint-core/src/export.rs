@@ -0,0 +1,208 @@
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::naming;
+use crate::passwords::Passwords;
+use crate::pdf::{self, CombineOptions, PdfPrintDetails};
+use crate::range;
+
+/// Name of the manifest written next to the exported files
+pub const MANIFEST_FILE: &str = "manifest.json";
+/// Template used when none is given, `01-invoice.pdf` and so on
+pub const DEFAULT_NAME_TEMPLATE: &str = "{index}-{name}";
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Separate files in a directory
+    #[default]
+    Folder,
+    /// Separate files inside a single ZIP archive
+    Zip,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// File name template, with `{index}`, `{name}`, `{pages}` and `{range}` placeholders
+    pub name_template: String,
+    pub combine_options: CombineOptions,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        return Self {
+            format: ExportFormat::default(),
+            name_template: DEFAULT_NAME_TEMPLATE.to_string(),
+            combine_options: CombineOptions::default(),
+        };
+    }
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ManifestEntry {
+    /// Name of the exported file
+    pub file: String,
+    pub source: String,
+    /// 1-based pages taken from the source, unset when all of them were
+    pub range: Option<String>,
+    pub pages: i32,
+}
+
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct ExportManifest {
+    pub files: Vec<ManifestEntry>,
+    /// Name the manifest was written under, numbered when the folder already had one
+    #[serde(skip)]
+    pub manifest_file: String,
+}
+
+/// Where exported files end up
+enum Sink {
+    Folder(PathBuf),
+    Zip(ZipWriter<BufWriter<File>>),
+}
+
+impl Sink {
+    fn open(destination: &Path, format: ExportFormat) -> Result<Self, String> {
+        return match format {
+            ExportFormat::Folder => {
+                std::fs::create_dir_all(destination).map_err(|e| return e.to_string())?;
+                Ok(Self::Folder(destination.to_path_buf()))
+            }
+            ExportFormat::Zip => {
+                let file = File::create(destination).map_err(|e| return e.to_string())?;
+                Ok(Self::Zip(ZipWriter::new(BufWriter::new(file))))
+            }
+        };
+    }
+
+    /// Lower cased names already in the destination, which exported files must not replace
+    fn existing_names(&self) -> Result<HashSet<String>, String> {
+        return match self {
+            Self::Folder(dir) => Ok(std::fs::read_dir(dir)
+                .map_err(|e| return e.to_string())?
+                .filter_map(Result::ok)
+                .map(|entry| return entry.file_name().to_string_lossy().to_lowercase())
+                .collect()),
+            Self::Zip(_) => Ok(HashSet::new()),
+        };
+    }
+
+    fn add(&mut self, name: &str, bytes: &[u8]) -> Result<(), String> {
+        match self {
+            Self::Folder(dir) => {
+                OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(dir.join(name))
+                    .and_then(|mut file| return file.write_all(bytes))
+                    .map_err(|e| return format!("{name}: {e}"))?;
+            }
+            Self::Zip(writer) => {
+                let options =
+                    SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+                writer
+                    .start_file(name, options)
+                    .map_err(|e| return e.to_string())?;
+                writer.write_all(bytes).map_err(|e| return e.to_string())?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn finish(self) -> Result<(), String> {
+        if let Self::Zip(writer) = self {
+            writer
+                .finish()
+                .map_err(|e| return e.to_string())?
+                .flush()
+                .map_err(|e| return e.to_string())?;
+        }
+
+        return Ok(());
+    }
+}
+
+/// Picks a manifest name that is not taken yet, `manifest (2).json` when `manifest.json` is
+fn manifest_name(taken: &mut HashSet<String>) -> String {
+    let stem = MANIFEST_FILE.trim_end_matches(".json");
+    let mut name = MANIFEST_FILE.to_string();
+    let mut counter = 2;
+    while !taken.insert(name.to_lowercase()) {
+        name = format!("{stem} ({counter}).json");
+        counter += 1;
+    }
+
+    return name;
+}
+
+/// Writes each document as its own PDF, with its print range applied and images converted,
+/// into a folder or a ZIP archive at `destination`, along with a manifest describing them.
+/// Files already in the folder are kept, exported files that would clash get a counter.
+///
+/// # Errors
+///
+/// Returns an error when a document can not be combined or the destination can not be written.
+pub fn export_documents(
+    pdfs: Vec<PdfPrintDetails>,
+    destination: &Path,
+    options: &ExportOptions,
+    passwords: &Passwords,
+) -> Result<ExportManifest, String> {
+    let mut sink = Sink::open(destination, options.format)?;
+    let mut taken = sink.existing_names()?;
+    let manifest_file = manifest_name(&mut taken);
+    let mut manifest = ExportManifest {
+        manifest_file: manifest_file.clone(),
+        ..ExportManifest::default()
+    };
+    let count = pdfs.len();
+
+    for (index, pdf_detail) in pdfs.into_iter().enumerate() {
+        let source = pdf_detail.path.clone();
+        let range = pdf_detail
+            .print_range
+            .as_deref()
+            .map(range::format_page_range);
+
+        let document = pdf::extract_document(pdf_detail, options.combine_options, passwords)?;
+        let pages = document.page_count().map_err(|e| return e.to_string())?;
+        let bytes = pdf::document_bytes(&document)?;
+
+        let file = naming::pdf_file_name(
+            &options.name_template,
+            &[
+                ("index", naming::padded_index(index + 1, count)),
+                ("name", naming::file_stem(&source)),
+                ("pages", pages.to_string()),
+                (
+                    "range",
+                    range.clone().unwrap_or_else(|| return "all".to_string()),
+                ),
+            ],
+            &mut taken,
+        );
+        sink.add(&file, &bytes)?;
+
+        manifest.files.push(ManifestEntry {
+            file,
+            source,
+            range,
+            pages,
+        });
+    }
+
+    let manifest_json = serde_json::to_vec_pretty(&manifest).map_err(|e| return e.to_string())?;
+    sink.add(&manifest_file, &manifest_json)?;
+    sink.finish()?;
+
+    return Ok(manifest);
+}
@@ -0,0 +1,32 @@
+use std::fs::File;
+use std::io::Read;
+use std::{ffi::OsStr, path::Path};
+
+use xxhash_rust::xxh3::Xxh3;
+
+/// Utility function for getting the extension from a file name
+#[must_use]
+pub fn get_extension_from_filename(filename: &str) -> Option<&str> {
+    return Path::new(filename).extension().and_then(OsStr::to_str);
+}
+
+/// Hashes a file's contents, to tell later whether it has changed
+///
+/// # Errors
+///
+/// Returns an error when the file can not be read.
+pub fn hash_file(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| return e.to_string())?;
+    let mut hasher = Xxh3::new();
+    let mut buffer = vec![0; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| return e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    return Ok(format!("{:032x}", hasher.digest128()));
+}
@@ -0,0 +1,21 @@
+//! Scanning, combining and printing of PDF documents, independent of the app shell.
+
+pub mod blank;
+pub mod entry_id;
+pub mod export;
+pub mod file_utils;
+pub mod metadata;
+pub mod naming;
+pub mod passwords;
+pub mod pdf;
+pub mod preflight;
+pub mod printing;
+pub mod range;
+pub mod redact;
+pub mod render;
+pub mod repair;
+pub mod scan;
+pub mod scan_filter;
+pub mod select;
+pub mod split;
+pub mod text_index;
@@ -0,0 +1,266 @@
+use std::io::Write;
+
+use mupdf::pdf::PdfDocument;
+use mupdf::{Buffer, Document, MetadataName};
+use time::OffsetDateTime;
+
+/// Document information read from a PDF's Info dictionary
+#[derive(serde::Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    pub producer: Option<String>,
+    /// RFC 3339, such as `2024-03-01T09:30:00+01:00`
+    pub created: Option<String>,
+    /// RFC 3339, such as `2024-03-01T09:30:00+01:00`
+    pub modified: Option<String>,
+    /// PDF version, such as `1.7`
+    pub version: Option<String>,
+}
+
+/// Document information to write into a saved PDF
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct MetadataOptions {
+    /// Defaults to the job name followed by the date
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    /// Used for the default title, callers fall back to the output file name
+    pub job_name: Option<String>,
+}
+
+fn read(document: &Document, name: MetadataName) -> Option<String> {
+    return document
+        .metadata(name)
+        .ok()
+        .map(|value| return value.trim().to_string())
+        .filter(|value| return !value.is_empty());
+}
+
+/// Converts a PDF date such as `D:20240301093000+01'00'` to RFC 3339. Missing parts default to
+/// the start of the period and a missing time zone to UTC.
+#[must_use]
+pub fn parse_pdf_date(value: &str) -> Option<String> {
+    let value = value.trim();
+    let value = value.strip_prefix("D:").unwrap_or(value);
+    let digits: String = value.chars().take_while(char::is_ascii_digit).collect();
+    if digits.len() < 4 {
+        return None;
+    }
+    let part = |from: usize, default: &'static str| {
+        return digits.get(from..from + 2).unwrap_or(default);
+    };
+
+    let zone = &value[digits.len()..];
+    let offset = match zone.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let zone: String = zone.chars().filter(char::is_ascii_digit).collect();
+            format!(
+                "{sign}{}:{}",
+                zone.get(0..2).unwrap_or("00"),
+                zone.get(2..4).unwrap_or("00")
+            )
+        }
+        _ => "Z".to_string(),
+    };
+
+    return Some(format!(
+        "{}-{}-{}T{}:{}:{}{offset}",
+        &digits[0..4],
+        part(4, "01"),
+        part(6, "01"),
+        part(8, "00"),
+        part(10, "00"),
+        part(12, "00"),
+    ));
+}
+
+/// Reads the document information, leaving out anything that is not set
+#[must_use]
+pub fn read_metadata(document: &Document) -> DocumentMetadata {
+    return DocumentMetadata {
+        title: read(document, MetadataName::Title),
+        author: read(document, MetadataName::Author),
+        subject: read(document, MetadataName::Subject),
+        keywords: read(document, MetadataName::Keywords),
+        producer: read(document, MetadataName::Producer),
+        created: read(document, MetadataName::CreationDate)
+            .and_then(|date| return parse_pdf_date(&date)),
+        modified: read(document, MetadataName::ModDate)
+            .and_then(|date| return parse_pdf_date(&date)),
+        version: read(document, MetadataName::Format).map(|format| {
+            return format.trim_start_matches("PDF").trim().to_string();
+        }),
+    };
+}
+
+fn pdf_date(date: OffsetDateTime) -> String {
+    return format!(
+        "D:{:04}{:02}{:02}{:02}{:02}{:02}Z",
+        date.year(),
+        u8::from(date.month()),
+        date.day(),
+        date.hour(),
+        date.minute(),
+        date.second()
+    );
+}
+
+fn xmp_date(date: OffsetDateTime) -> String {
+    return format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        date.year(),
+        u8::from(date.month()),
+        date.day(),
+        date.hour(),
+        date.minute(),
+        date.second()
+    );
+}
+
+fn escape_xml(value: &str) -> String {
+    return value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;");
+}
+
+/// Builds the XMP packet describing the document, matching its Info dictionary
+fn xmp_packet(entries: &[(&str, String)], now: OffsetDateTime) -> String {
+    let value = |key: &str| {
+        return entries
+            .iter()
+            .find(|(name, _)| return *name == key)
+            .map(|(_, value)| return escape_xml(value));
+    };
+
+    let mut properties = vec![];
+    if let Some(title) = value("Title") {
+        properties.push(format!(
+            "<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{title}</rdf:li></rdf:Alt></dc:title>"
+        ));
+    }
+    if let Some(author) = value("Author") {
+        properties.push(format!(
+            "<dc:creator><rdf:Seq><rdf:li>{author}</rdf:li></rdf:Seq></dc:creator>"
+        ));
+    }
+    if let Some(subject) = value("Subject") {
+        properties.push(format!(
+            "<dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{subject}</rdf:li></rdf:Alt></dc:description>"
+        ));
+    }
+    if let Some(keywords) = value("Keywords") {
+        properties.push(format!("<pdf:Keywords>{keywords}</pdf:Keywords>"));
+    }
+    let date = xmp_date(now);
+    properties.push(format!("<xmp:CreateDate>{date}</xmp:CreateDate>"));
+    properties.push(format!("<xmp:ModifyDate>{date}</xmp:ModifyDate>"));
+
+    return format!(
+        concat!(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n",
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n",
+            "<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n",
+            "<rdf:Description rdf:about=\"\"",
+            " xmlns:dc=\"http://purl.org/dc/elements/1.1/\"",
+            " xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\"",
+            " xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\">\n",
+            "{}\n",
+            "</rdf:Description>\n",
+            "</rdf:RDF>\n",
+            "</x:xmpmeta>\n",
+            "<?xpacket end=\"w\"?>"
+        ),
+        properties.join("\n")
+    );
+}
+
+/// Writes the document information into both the Info dictionary and an XMP metadata stream.
+/// Without a title, the job name, or `default_name` when there is none, and the date are used.
+///
+/// # Errors
+///
+/// Returns an error when mupdf fails to create or update the metadata objects.
+pub fn write_metadata(
+    document: &mut PdfDocument,
+    options: &MetadataOptions,
+    default_name: &str,
+) -> Result<(), String> {
+    let now = OffsetDateTime::now_utc();
+    let title = options.title.clone().unwrap_or_else(|| {
+        return format!(
+            "{} {:04}-{:02}-{:02}",
+            options.job_name.as_deref().unwrap_or(default_name),
+            now.year(),
+            u8::from(now.month()),
+            now.day()
+        );
+    });
+
+    let mut entries = vec![("Title", title)];
+    for (key, value) in [
+        ("Author", &options.author),
+        ("Subject", &options.subject),
+        ("Keywords", &options.keywords),
+    ] {
+        if let Some(value) = value {
+            entries.push((key, value.clone()));
+        }
+    }
+
+    let mut info = document.new_dict().map_err(|e| return e.to_string())?;
+    for (key, value) in entries
+        .iter()
+        .map(|(key, value)| return (*key, value.clone()))
+        .chain([("CreationDate", pdf_date(now)), ("ModDate", pdf_date(now))])
+    {
+        let value = document
+            .new_string(&value)
+            .map_err(|e| return e.to_string())?;
+        info.dict_put(key, value)
+            .map_err(|e| return e.to_string())?;
+    }
+    let info = document
+        .add_object(&info)
+        .map_err(|e| return e.to_string())?;
+    let mut trailer = document.trailer().map_err(|e| return e.to_string())?;
+    trailer
+        .dict_put("Info", info)
+        .map_err(|e| return e.to_string())?;
+
+    let mut xmp = document.new_dict().map_err(|e| return e.to_string())?;
+    xmp.dict_put(
+        "Type",
+        document
+            .new_name("Metadata")
+            .map_err(|e| return e.to_string())?,
+    )
+    .map_err(|e| return e.to_string())?;
+    xmp.dict_put(
+        "Subtype",
+        document.new_name("XML").map_err(|e| return e.to_string())?,
+    )
+    .map_err(|e| return e.to_string())?;
+    let mut xmp = document
+        .add_object(&xmp)
+        .map_err(|e| return e.to_string())?;
+    let mut packet = Buffer::new();
+    packet
+        .write_all(xmp_packet(&entries, now).as_bytes())
+        .map_err(|e| return e.to_string())?;
+    xmp.write_stream_buffer(&packet)
+        .map_err(|e| return e.to_string())?;
+
+    let mut catalog = document.catalog().map_err(|e| return e.to_string())?;
+    catalog
+        .dict_put("Metadata", xmp)
+        .map_err(|e| return e.to_string())?;
+
+    return Ok(());
+}
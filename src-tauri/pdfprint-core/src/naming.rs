@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Characters that are not allowed in file names on at least one platform
+const RESERVED_CHARACTERS: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// Fills the `{key}` placeholders of a file name template, leaving unknown ones as they are
+#[must_use]
+pub fn render_template(template: &str, values: &[(&str, String)]) -> String {
+    return values
+        .iter()
+        .fold(template.to_string(), |name, (key, value)| {
+            return name.replace(&format!("{{{key}}}"), value);
+        });
+}
+
+/// Replaces characters that can not appear in a file name, so a template can never
+/// point outside the output directory
+#[must_use]
+pub fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_control() || RESERVED_CHARACTERS.contains(&c) {
+                return '_';
+            }
+            return c;
+        })
+        .collect();
+    let trimmed = sanitized.trim().trim_matches('.');
+
+    if trimmed.is_empty() {
+        return "document".to_string();
+    }
+    return trimmed.to_string();
+}
+
+/// Renders a template into a sanitized PDF file name that is not in `taken` yet, and takes it
+pub fn pdf_file_name(
+    template: &str,
+    values: &[(&str, String)],
+    taken: &mut HashSet<String>,
+) -> String {
+    let rendered = sanitize_file_name(&render_template(template, values));
+    let stem = rendered
+        .strip_suffix(".pdf")
+        .or_else(|| return rendered.strip_suffix(".PDF"))
+        .unwrap_or(&rendered)
+        .to_string();
+
+    let mut name = format!("{stem}.pdf");
+    let mut counter = 2;
+    while !taken.insert(name.to_lowercase()) {
+        name = format!("{stem} ({counter}).pdf");
+        counter += 1;
+    }
+
+    return name;
+}
+
+/// The file name of a path without its extension, used as the `{name}` placeholder
+#[must_use]
+pub fn file_stem(path: &str) -> String {
+    return Path::new(path)
+        .file_stem()
+        .map_or_else(String::new, |stem| {
+            return stem.to_string_lossy().to_string();
+        });
+}
+
+/// Zero pads a 1-based index to the width of the largest index, so names sort in order
+#[must_use]
+pub fn padded_index(index: usize, count: usize) -> String {
+    let width = count.to_string().len();
+    return format!("{index:0width$}");
+}
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+
+use mupdf::Document;
+
+/// Passwords of the documents unlocked this session, by path. Never written to disk.
+#[derive(Default)]
+pub struct Passwords {
+    by_path: Mutex<BTreeMap<String, String>>,
+}
+
+fn key(path: &Path) -> String {
+    return path.to_string_lossy().to_string();
+}
+
+impl Passwords {
+    fn remembered(&self, path: &Path) -> Option<String> {
+        return self
+            .by_path
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key(path))
+            .cloned();
+    }
+
+    /// Authenticates a document that needs a password with the one unlocked for its path
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the document needs a password that was not unlocked, or no longer matches.
+    pub fn authenticate(&self, document: &mut Document, path: &Path) -> Result<(), String> {
+        if !document
+            .needs_password()
+            .map_err(|e| return e.to_string())?
+        {
+            return Ok(());
+        }
+
+        let Some(password) = self.remembered(path) else {
+            return Err(format!("{} is password protected", path.to_string_lossy()));
+        };
+        if !document
+            .authenticate(&password)
+            .map_err(|e| return e.to_string())?
+        {
+            return Err(format!(
+                "The password for {} no longer opens it",
+                path.to_string_lossy()
+            ));
+        }
+
+        return Ok(());
+    }
+
+    /// Checks the password opens the document and keeps it for the rest of the session
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the document can not be opened or the password is wrong.
+    pub fn unlock(&self, path: &Path, password: &str) -> Result<(), String> {
+        let mut document =
+            Document::open(&path.to_string_lossy()).map_err(|e| return e.to_string())?;
+        if document
+            .needs_password()
+            .map_err(|e| return e.to_string())?
+            && !document
+                .authenticate(password)
+                .map_err(|e| return e.to_string())?
+        {
+            return Err("Wrong password".to_string());
+        }
+
+        self.by_path
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key(path), password.to_string());
+
+        return Ok(());
+    }
+
+    /// Whether the document needs a password, whether or not it was unlocked this session
+    #[must_use]
+    pub fn is_encrypted(path: &Path) -> bool {
+        return Document::open(&path.to_string_lossy())
+            .and_then(|document| return document.needs_password())
+            .unwrap_or(false);
+    }
+
+    /// Whether the document needs a password that has not been unlocked this session
+    #[must_use]
+    pub fn is_locked(&self, path: &Path) -> bool {
+        return Document::open(&path.to_string_lossy())
+            .map(|mut document| return self.authenticate(&mut document, path).is_err())
+            .unwrap_or(false);
+    }
+}
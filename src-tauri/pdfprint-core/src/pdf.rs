@@ -0,0 +1,719 @@
+use log::warn;
+use mupdf::pdf::{Encryption, PdfGraftMap, PdfObject, PdfWriteOptions, Permission};
+use mupdf::{Document, DocumentWriter, Matrix, Rect, Size};
+
+use std::path::Path;
+
+pub use mupdf::pdf::PdfDocument;
+
+use crate::blank::{self, BlankOptions};
+use crate::file_utils;
+use crate::passwords::Passwords;
+use crate::redact::{Redaction, Redactor};
+use crate::select::PageSelector;
+use crate::text_index;
+
+/// Image formats mupdf can open as documents
+pub const IMAGE_EXTENSIONS: [&str; 5] = ["jpg", "jpeg", "png", "tif", "tiff"];
+/// Plain text formats mupdf lays out into pages
+pub const TEXT_EXTENSIONS: [&str; 1] = ["txt"];
+/// Font size text documents are laid out with, in points
+const TEXT_FONT_SIZE: f32 = 11.0;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct PdfPrintDetails {
+    pub name: String,
+    pub pages: i32,
+    pub size: u64,
+    pub path: String,
+    /// 0-based pages to include, after collating
+    pub print_range: Option<Vec<i32>>,
+    /// Interleaves the pages with a scan of the back sides
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collate: Option<Collate>,
+    /// Keeps only the pages whose text matches, narrowing the print range when both are set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub select: Option<PageSelector>,
+    /// Content removed before the pages are printed or saved
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redactions: Vec<Redaction>,
+}
+
+/// Back sides of a document scanned on a simplex feeder, fronts and backs as separate files
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Collate {
+    /// Document holding the back sides
+    pub backs: String,
+    /// The backs were scanned by turning the whole stack over, so the last page comes first
+    #[serde(default)]
+    pub reverse_backs: bool,
+}
+
+/// Page size used when converting images and text into PDF pages
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImagePageSize {
+    #[default]
+    Letter,
+    Legal,
+    A4,
+    /// Use the size of the image itself, text falls back to letter
+    Original,
+}
+
+/// How an image is placed on its page
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFit {
+    /// Scale to fit entirely within the page, keeping the aspect ratio
+    #[default]
+    Contain,
+    /// Scale to cover the whole page, keeping the aspect ratio and cropping the overflow
+    Cover,
+    /// Scale to the page size, ignoring the aspect ratio
+    Stretch,
+    /// Keep the image size and center it on the page
+    Center,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(default)]
+pub struct CombineOptions {
+    pub image_page_size: ImagePageSize,
+    pub image_fit: ImageFit,
+    /// Leaves out pages recognised as blank, such as the empty backs of scanned sheets
+    pub skip_blank_pages: Option<BlankOptions>,
+}
+
+#[must_use]
+pub fn is_image_path(path: &Path) -> bool {
+    return file_utils::get_extension_from_filename(&path.to_string_lossy())
+        .is_some_and(|ext| return IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+}
+
+#[must_use]
+pub fn is_text_path(path: &Path) -> bool {
+    return file_utils::get_extension_from_filename(&path.to_string_lossy())
+        .is_some_and(|ext| return TEXT_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+}
+
+/// Whether the file is an image or text that is converted to PDF pages when combined
+#[must_use]
+pub fn is_converted_path(path: &Path) -> bool {
+    return is_image_path(path) || is_text_path(path);
+}
+
+/// Whether the file is a PDF, an image or text that can be listed and combined
+#[must_use]
+pub fn is_document_path(path: &Path) -> bool {
+    return is_converted_path(path)
+        || file_utils::get_extension_from_filename(&path.to_string_lossy())
+            .is_some_and(|ext| return ext.eq_ignore_ascii_case("pdf"));
+}
+
+/// Opens a document, authenticating it with the password unlocked for it in `passwords`
+///
+/// # Errors
+///
+/// Returns an error when the file can not be opened or is locked.
+pub fn open_document(path: &Path, passwords: &Passwords) -> Result<Document, String> {
+    let mut document = Document::open(&path.to_string_lossy()).map_err(|e| return e.to_string())?;
+    passwords.authenticate(&mut document, path)?;
+
+    return Ok(document);
+}
+
+/// Opens a PDF, authenticating it with the password unlocked for it in `passwords`
+///
+/// # Errors
+///
+/// Returns an error when the file is not a readable PDF or is locked.
+pub fn open_pdf(path: &Path, passwords: &Passwords) -> Result<PdfDocument, String> {
+    let mut document =
+        PdfDocument::open(&path.to_string_lossy()).map_err(|e| return e.to_string())?;
+    passwords.authenticate(&mut document, path)?;
+
+    return Ok(document);
+}
+
+/// Counts the pages of a PDF, image or text document, text laid out on the default page size
+///
+/// # Errors
+///
+/// Returns an error when the document can not be opened or is locked.
+pub fn count_pages(path: &Path, passwords: &Passwords) -> Result<i32, String> {
+    if is_text_path(path) {
+        let document = open_text(path, ImagePageSize::default())?;
+        return document.page_count().map_err(|e| return e.to_string());
+    }
+    if is_image_path(path) {
+        let document = Document::open(&path.to_string_lossy()).map_err(|e| return e.to_string())?;
+        return document.page_count().map_err(|e| return e.to_string());
+    }
+
+    let document = open_pdf(path, passwords)?;
+    return document.page_count().map_err(|e| return e.to_string());
+}
+
+/// Short and long side of a page size in points, `None` for the original size
+const fn page_sides(page_size: ImagePageSize) -> Option<(f32, f32)> {
+    return match page_size {
+        ImagePageSize::Letter => Some((612.0, 792.0)),
+        ImagePageSize::Legal => Some((612.0, 1008.0)),
+        ImagePageSize::A4 => Some((595.28, 841.89)),
+        ImagePageSize::Original => None,
+    };
+}
+
+/// Works out the page box for an image and the matrix placing the image on it
+#[allow(clippy::suboptimal_flops)]
+fn layout_image(bounds: &Rect, options: CombineOptions) -> (Rect, Matrix) {
+    let image_width = bounds.x1 - bounds.x0;
+    let image_height = bounds.y1 - bounds.y0;
+
+    let Some((short_side, long_side)) = page_sides(options.image_page_size) else {
+        let media_box = Rect::new(0.0, 0.0, image_width, image_height);
+        return (
+            media_box,
+            Matrix::new(1.0, 0.0, 0.0, 1.0, -bounds.x0, -bounds.y0),
+        );
+    };
+
+    // Match the page orientation to the image
+    let (page_width, page_height) = if image_width > image_height {
+        (long_side, short_side)
+    } else {
+        (short_side, long_side)
+    };
+
+    let (scale_x, scale_y) = match options.image_fit {
+        ImageFit::Contain => {
+            let scale = (page_width / image_width).min(page_height / image_height);
+            (scale, scale)
+        }
+        ImageFit::Cover => {
+            let scale = (page_width / image_width).max(page_height / image_height);
+            (scale, scale)
+        }
+        ImageFit::Stretch => (page_width / image_width, page_height / image_height),
+        ImageFit::Center => (1.0, 1.0),
+    };
+
+    let offset_x = (page_width - image_width * scale_x) / 2.0;
+    let offset_y = (page_height - image_height * scale_y) / 2.0;
+
+    return (
+        Rect::new(0.0, 0.0, page_width, page_height),
+        Matrix::new(
+            scale_x,
+            0.0,
+            0.0,
+            scale_y,
+            offset_x - bounds.x0 * scale_x,
+            offset_y - bounds.y0 * scale_y,
+        ),
+    );
+}
+
+/// Opens a text document laid out in portrait on the page size
+///
+/// # Errors
+///
+/// Returns an error when the file can not be opened or laid out.
+pub fn open_text(path: &Path, page_size: ImagePageSize) -> Result<Document, String> {
+    let mut document = Document::open(&path.to_string_lossy()).map_err(|e| return e.to_string())?;
+    let (width, height) = page_sides(page_size).unwrap_or((612.0, 792.0));
+    document
+        .layout(width, height, TEXT_FONT_SIZE)
+        .map_err(|e| return e.to_string())?;
+
+    return Ok(document);
+}
+
+/// Draws every page of a document onto new PDF pages, placed by `layout`
+fn rewrite_as_pdf<F>(document: &Document, layout: F) -> Result<PdfDocument, String>
+where
+    F: Fn(&Rect) -> (Rect, Matrix),
+{
+    let page_count = document.page_count().map_err(|e| return e.to_string())?;
+
+    let temp_file =
+        tempfile::NamedTempFile::with_suffix(".pdf").map_err(|e| return e.to_string())?;
+    let temp_path = temp_file.path().to_string_lossy().to_string();
+
+    {
+        let mut writer =
+            DocumentWriter::new(&temp_path, "pdf", "").map_err(|e| return e.to_string())?;
+
+        for i in 0..page_count {
+            let page = document.load_page(i).map_err(|e| return e.to_string())?;
+            let bounds = page.bounds().map_err(|e| return e.to_string())?;
+            let (media_box, ctm) = layout(&bounds);
+
+            let device = writer
+                .begin_page(media_box)
+                .map_err(|e| return e.to_string())?;
+            page.run(&device, &ctm).map_err(|e| return e.to_string())?;
+            writer.end_page(device).map_err(|e| return e.to_string())?;
+        }
+    }
+
+    // Read it back into memory so the temporary file can be removed straight away
+    let bytes = std::fs::read(temp_file.path()).map_err(|e| return e.to_string())?;
+    drop(temp_file);
+
+    return PdfDocument::from_bytes(&bytes).map_err(|e| return e.to_string());
+}
+
+/// Converts every page of an image (multi-page TIFFs included) into a PDF page
+///
+/// # Errors
+///
+/// Returns an error when the image can not be opened or its pages written.
+pub fn convert_image_to_pdf(path: &Path, options: CombineOptions) -> Result<PdfDocument, String> {
+    let image_doc = Document::open(&path.to_string_lossy()).map_err(|e| return e.to_string())?;
+
+    return rewrite_as_pdf(&image_doc, |bounds| return layout_image(bounds, options));
+}
+
+/// Lays out a text document on pages of the image page size and converts them to PDF pages
+///
+/// # Errors
+///
+/// Returns an error when the text can not be opened, laid out or its pages written.
+pub fn convert_text_to_pdf(path: &Path, options: CombineOptions) -> Result<PdfDocument, String> {
+    let text_doc = open_text(path, options.image_page_size)?;
+
+    return rewrite_as_pdf(&text_doc, |bounds| {
+        return (
+            Rect::new(0.0, 0.0, bounds.x1 - bounds.x0, bounds.y1 - bounds.y0),
+            Matrix::new(1.0, 0.0, 0.0, 1.0, -bounds.x0, -bounds.y0),
+        );
+    });
+}
+
+/// Options documents are saved with. Redacting a page leaves the content it covered in objects
+/// nothing refers to anymore, so unused objects are always dropped rather than written out.
+fn write_options() -> PdfWriteOptions {
+    let mut options = PdfWriteOptions::default();
+    options.set_garbage_level(3).set_clean(true);
+
+    return options;
+}
+
+/// Writes a document to `path`, replacing any existing file
+///
+/// # Errors
+///
+/// Returns an error when the file can not be written.
+pub fn save_document(document: &PdfDocument, path: &Path) -> Result<(), String> {
+    return document
+        .save_with_options(&path.to_string_lossy(), write_options())
+        .map_err(|e| return e.to_string());
+}
+
+/// Saves a document the way [`save_document`] does, into memory
+///
+/// # Errors
+///
+/// Returns an error when the document can not be written.
+pub fn document_bytes(document: &PdfDocument) -> Result<Vec<u8>, String> {
+    let temp_file =
+        tempfile::NamedTempFile::with_suffix(".pdf").map_err(|e| return e.to_string())?;
+    save_document(document, temp_file.path())?;
+
+    return std::fs::read(temp_file.path()).map_err(|e| return e.to_string());
+}
+
+/// Encryption used for saved documents
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EncryptionMethod {
+    Aes128,
+    #[default]
+    Aes256,
+}
+
+/// What can be done with an encrypted document without its owner password
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Permissions {
+    pub print: bool,
+    pub copy: bool,
+    pub modify: bool,
+    /// Adding comments and filling in forms
+    pub annotate: bool,
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        return Self {
+            print: true,
+            copy: false,
+            modify: false,
+            annotate: false,
+        };
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct EncryptionOptions {
+    /// Needed to open the document, when empty it opens without one but keeps its restrictions
+    pub user_password: String,
+    /// Lifts the restrictions, the user password is used when empty
+    pub owner_password: String,
+    pub method: EncryptionMethod,
+    pub permissions: Permissions,
+}
+
+impl EncryptionOptions {
+    fn write_options(&self) -> Result<PdfWriteOptions, String> {
+        let owner_password = if self.owner_password.is_empty() {
+            &self.user_password
+        } else {
+            &self.owner_password
+        };
+        if owner_password.is_empty() {
+            return Err("A password is needed to encrypt the document".to_string());
+        }
+
+        let mut permissions = Permission::empty();
+        for (allowed, permission) in [
+            (
+                self.permissions.print,
+                Permission::PRINT | Permission::PRINT_HQ,
+            ),
+            (self.permissions.copy, Permission::COPY),
+            (
+                self.permissions.modify,
+                Permission::MODIFY | Permission::ASSEMBLE,
+            ),
+            (
+                self.permissions.annotate,
+                Permission::ANNOTATE | Permission::FORM,
+            ),
+        ] {
+            permissions.set(permission, allowed);
+        }
+
+        let mut options = write_options();
+        options
+            .set_encryption(match self.method {
+                EncryptionMethod::Aes128 => Encryption::Aes128,
+                EncryptionMethod::Aes256 => Encryption::Aes256,
+            })
+            .set_permissions(permissions)
+            .set_owner_password(owner_password)
+            .set_user_password(&self.user_password);
+
+        return Ok(options);
+    }
+}
+
+/// Writes a document to `path` encrypted with the given passwords and permissions
+///
+/// # Errors
+///
+/// Returns an error when no password is given or the file can not be written.
+pub fn save_encrypted_document(
+    document: &PdfDocument,
+    path: &Path,
+    encryption: &EncryptionOptions,
+) -> Result<(), String> {
+    return document
+        .save_with_options(&path.to_string_lossy(), encryption.write_options()?)
+        .map_err(|e| return e.to_string());
+}
+
+/// Page attributes a page can inherit from its ancestors in the page tree
+const INHERITABLE_ATTRIBUTES: [&str; 4] = ["MediaBox", "CropBox", "Rotate", "Resources"];
+/// Page tree levels searched for inherited attributes, guarding against cycles in broken files
+const MAX_PAGE_TREE_DEPTH: u8 = 32;
+
+/// Looks an attribute up on the node, then on its ancestors in the page tree
+fn inherited_attribute(
+    node: &PdfObject,
+    key: &str,
+    depth: u8,
+) -> Result<Option<PdfObject>, mupdf::Error> {
+    if let Some(value) = node.get_dict(key)? {
+        return Ok(Some(value));
+    }
+    if depth == 0 {
+        return Ok(None);
+    }
+
+    return match node.get_dict("Parent")? {
+        Some(parent) => inherited_attribute(&parent, key, depth - 1),
+        None => Ok(None),
+    };
+}
+
+/// Copies a page into `destination_doc`, resolving inherited attributes so the copy stands on
+/// its own outside the source page tree
+fn add_page_to(
+    destination_doc: &mut PdfDocument,
+    src_page: &PdfObject,
+    graft_map: &mut PdfGraftMap,
+) -> Result<PdfObject, mupdf::Error> {
+    let mut dst_page = destination_doc.new_dict()?;
+    dst_page.dict_put("Type", destination_doc.new_name("Page")?)?;
+
+    for dict_key in INHERITABLE_ATTRIBUTES {
+        if let Ok(Some(dict)) = inherited_attribute(src_page, dict_key, MAX_PAGE_TREE_DEPTH) {
+            let grafted = graft_map.graft_object(&dict)?;
+            dst_page.dict_put(dict_key, grafted)?;
+        }
+    }
+    if let Ok(Some(contents)) = src_page.get_dict("Contents") {
+        let grafted = graft_map.graft_object(&contents)?;
+        dst_page.dict_put("Contents", grafted)?;
+    }
+
+    return destination_doc.add_object(&dst_page);
+}
+
+/// Opens a document, converting images and text to PDF pages
+fn open_as_pdf(
+    path: &Path,
+    options: CombineOptions,
+    passwords: &Passwords,
+) -> Result<PdfDocument, String> {
+    if is_text_path(path) {
+        return convert_text_to_pdf(path, options);
+    }
+    if is_image_path(path) {
+        return convert_image_to_pdf(path, options);
+    }
+
+    return open_pdf(path, passwords);
+}
+
+/// Order of the pages of a collated document, as source index (0 for the fronts, 1 for the
+/// backs) and page. The backs can be one page short when the last sheet is single sided.
+///
+/// # Errors
+///
+/// Returns an error when the back sides do not match the fronts in page count.
+pub fn collate_order(
+    fronts: i32,
+    backs: i32,
+    reverse_backs: bool,
+) -> Result<Vec<(usize, i32)>, String> {
+    if backs != fronts && backs != fronts - 1 {
+        return Err(format!(
+            "Can not collate {fronts} front pages with {backs} back pages"
+        ));
+    }
+
+    let mut order = vec![];
+    for page in 0..fronts {
+        order.push((0, page));
+        if page < backs {
+            let back = if reverse_backs {
+                backs - 1 - page
+            } else {
+                page
+            };
+            order.push((1, back));
+        }
+    }
+
+    return Ok(order);
+}
+
+/// Drops the positions of pages that are blank, so they are not printed and do not count
+/// towards padding
+fn without_blank_pages(
+    sources: &[PdfDocument],
+    order: &[(usize, i32)],
+    positions: Vec<usize>,
+    options: BlankOptions,
+) -> Result<Vec<usize>, String> {
+    let mut kept = vec![];
+    for position in positions {
+        let (source, i) = order[position];
+        let page = sources[source]
+            .load_page(i)
+            .map_err(|e| return e.to_string())?;
+        if !blank::is_blank(&page, options)? {
+            kept.push(position);
+        }
+    }
+
+    return Ok(kept);
+}
+
+/// Opens a document along with the backs it is collated with, returning them and the order
+/// of their pages
+fn open_sources(
+    pdf_detail: &PdfPrintDetails,
+    options: CombineOptions,
+    passwords: &Passwords,
+) -> Result<(Vec<PdfDocument>, Vec<(usize, i32)>), String> {
+    let fronts = open_as_pdf(Path::new(&pdf_detail.path), options, passwords)?;
+    let front_count: i32 = fronts.page_count().map_err(|e| return e.to_string())?;
+    let mut sources = vec![fronts];
+
+    let order: Vec<(usize, i32)> = match &pdf_detail.collate {
+        Some(collate) => {
+            let backs = open_as_pdf(Path::new(&collate.backs), options, passwords)?;
+            let back_count = backs.page_count().map_err(|e| return e.to_string())?;
+            sources.push(backs);
+            collate_order(front_count, back_count, collate.reverse_backs)?
+        }
+        None => (0..front_count).map(|page| return (0, page)).collect(),
+    };
+
+    return Ok((sources, order));
+}
+
+/// Positions in `order` of the pages matching the selector
+fn selected_positions(
+    sources: &[PdfDocument],
+    order: &[(usize, i32)],
+    selector: &PageSelector,
+) -> Result<Vec<usize>, String> {
+    let texts = order
+        .iter()
+        .map(|(source, i)| -> Result<String, String> {
+            let page = sources[*source]
+                .load_page(*i)
+                .map_err(|e| return e.to_string())?;
+            return text_index::page_text(&page);
+        })
+        .collect::<Result<Vec<String>, String>>()?;
+
+    return selector.select(&texts);
+}
+
+/// Lists the 0-based pages, after collating, that a selector would keep
+///
+/// # Errors
+///
+/// Returns an error when the documents can not be opened or the selector pattern is invalid.
+pub fn preview_selection(
+    pdf_detail: &PdfPrintDetails,
+    selector: &PageSelector,
+    passwords: &Passwords,
+) -> Result<Vec<i32>, String> {
+    let (sources, order) = open_sources(pdf_detail, CombineOptions::default(), passwords)?;
+
+    return selected_positions(&sources, &order, selector)?
+        .into_iter()
+        .map(|position| return i32::try_from(position).map_err(|e| return e.to_string()))
+        .collect();
+}
+
+/// Appends the selected pages of a document to `destination_doc`, returning how many were added
+fn append_document(
+    destination_doc: &mut PdfDocument,
+    pdf_detail: PdfPrintDetails,
+    options: CombineOptions,
+    passwords: &Passwords,
+) -> Result<usize, String> {
+    // Checked before anything is copied, so a bad expression fails fast
+    let redactor = Redactor::new(&pdf_detail.redactions)?;
+    let (sources, order) = open_sources(&pdf_detail, options, passwords)?;
+
+    // A graft map can only copy from a single source document
+    let mut graft_maps = sources
+        .iter()
+        .map(|_| return destination_doc.new_graft_map())
+        .collect::<Result<Vec<PdfGraftMap>, _>>()
+        .map_err(|e| return e.to_string())?;
+
+    let positions: Vec<usize> = match pdf_detail.print_range {
+        Some(range) => range
+            .iter()
+            .map(|i| {
+                return usize::try_from(*i)
+                    .ok()
+                    .filter(|i| return *i < order.len())
+                    .ok_or_else(|| return format!("Page {} is out of range", i + 1));
+            })
+            .collect::<Result<_, _>>()?,
+        None => (0..order.len()).collect(),
+    };
+    let positions = match &pdf_detail.select {
+        Some(selector) => {
+            let selected = selected_positions(&sources, &order, selector)?;
+            positions
+                .into_iter()
+                .filter(|position| return selected.contains(position))
+                .collect()
+        }
+        None => positions,
+    };
+    let positions = match options.skip_blank_pages {
+        Some(blank_options) => without_blank_pages(&sources, &order, positions, blank_options)?,
+        None => positions,
+    };
+
+    let mut text_matches = 0;
+    for position in &positions {
+        let (source, i) = order[*position];
+        let page: PdfObject = sources[source]
+            .find_page(i)
+            .map_err(|e| return e.to_string())?;
+
+        let obj: PdfObject = add_page_to(destination_doc, &page, &mut graft_maps[source])
+            .map_err(|e| return e.to_string())?;
+        let index = destination_doc.page_count().unwrap_or(0);
+        destination_doc
+            .insert_page(index, &obj)
+            .map_err(|e| return e.to_string())?;
+
+        if !redactor.is_empty() {
+            let position = i32::try_from(*position).map_err(|e| return e.to_string())?;
+            text_matches += redactor.apply(destination_doc, index, position)?;
+        }
+    }
+    if redactor.has_text() && text_matches == 0 {
+        warn!("No text redaction matched anything in {}", pdf_detail.name);
+    }
+
+    return Ok(positions.len());
+}
+
+/// Builds a standalone PDF holding only the selected pages of a document
+///
+/// # Errors
+///
+/// Returns an error when the document can not be opened or its pages copied.
+pub fn extract_document(
+    pdf_detail: PdfPrintDetails,
+    options: CombineOptions,
+    passwords: &Passwords,
+) -> Result<PdfDocument, String> {
+    let mut document = PdfDocument::new();
+    append_document(&mut document, pdf_detail, options, passwords)?;
+
+    return Ok(document);
+}
+
+/// Combines the documents into one PDF, padding each to an even page count so they print double sided
+///
+/// # Errors
+///
+/// Returns an error when a document can not be opened or its pages copied.
+pub fn create_combined_pdf(
+    pdfs: Vec<PdfPrintDetails>,
+    options: CombineOptions,
+    passwords: &Passwords,
+) -> Result<PdfDocument, String> {
+    let mut temp_doc: PdfDocument = PdfDocument::new();
+
+    for pdf_detail in pdfs {
+        let added = append_document(&mut temp_doc, pdf_detail, options, passwords)?;
+
+        if added % 2 == 1 {
+            temp_doc
+                .new_page(Size::LETTER)
+                .map_err(|e| return e.to_string())?;
+        }
+    }
+
+    return Ok(temp_doc);
+}
@@ -0,0 +1,361 @@
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+
+use mupdf::pdf::{PdfDocument, PdfObject};
+
+use crate::passwords::Passwords;
+use crate::pdf::{self, CombineOptions, PdfPrintDetails};
+use crate::printing::{PrintOptions, Sides};
+
+/// Images finer than this, at the very least, are reported as slowing printing down
+const HIGH_RESOLUTION_DPI: f32 = 600.0;
+/// Blank pages combining pads documents with, Letter as width by height in points
+const PADDING_PAGE_SIZE: (i32, i32) = (612, 792);
+/// Levels of nested form XObjects searched for fonts and images
+const MAX_RESOURCE_DEPTH: u8 = 8;
+
+#[derive(serde::Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PreflightWarning {
+    /// Pages of different sizes across the pack, blank padding pages included, as width by
+    /// height in points
+    MixedPageSizes { sizes: Vec<String> },
+    /// 1-based pages, in the document as printed, with a rotation set
+    RotatedPages { document: String, pages: Vec<i32> },
+    /// Fonts the printer has to substitute
+    UnembeddedFonts {
+        document: String,
+        fonts: Vec<String>,
+    },
+    /// Password protected, `locked` when it can not be printed until unlocked
+    Encrypted { document: String, locked: bool },
+    /// Damaged, mupdf rebuilt it to read it so pages may be missing or wrong
+    Repaired { document: String },
+    /// Images of at least `dpi` on these 1-based pages
+    HighResolutionImages {
+        document: String,
+        pages: Vec<i32>,
+        dpi: u32,
+    },
+    /// 1-based pages using transparency, which some printers flatten badly
+    Transparency { document: String, pages: Vec<i32> },
+    /// Could not be combined at all
+    Unreadable { document: String, error: String },
+}
+
+#[derive(serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct PreflightReport {
+    pub warnings: Vec<PreflightWarning>,
+    /// Pages sent to the printer, including blank pages added to keep documents double sided
+    pub pages: i32,
+    /// Sheets of paper used by all copies, double sided unless one sided printing is asked for
+    pub sheets: i32,
+}
+
+/// What was found on the pages of one document
+#[derive(Default)]
+struct PageFindings {
+    rotated: Vec<i32>,
+    fonts: BTreeSet<String>,
+    high_resolution: Vec<i32>,
+    max_dpi: f32,
+    transparency: Vec<i32>,
+}
+
+fn name_of(object: &PdfObject, key: &str) -> Option<String> {
+    let value = object.get_dict(key).ok()??;
+    return value
+        .as_name()
+        .ok()
+        .map(|name| return String::from_utf8_lossy(name).to_string());
+}
+
+/// Entries of a dictionary held under `key`, such as the fonts of a resource dictionary
+fn dict_values(object: &PdfObject, key: &str) -> Vec<PdfObject> {
+    let Ok(Some(dict)) = object.get_dict(key) else {
+        return vec![];
+    };
+    let len = dict.dict_len().unwrap_or(0);
+
+    return (0..len)
+        .filter_map(|i| return dict.get_dict_val(i).ok().flatten())
+        .collect();
+}
+
+fn is_embedded(font: &PdfObject) -> bool {
+    if name_of(font, "Subtype").as_deref() == Some("Type3") {
+        return true;
+    }
+    // Composite fonts keep their descriptor on the descendant font
+    let descriptor_owner = if name_of(font, "Subtype").as_deref() == Some("Type0") {
+        font.get_dict("DescendantFonts")
+            .ok()
+            .flatten()
+            .and_then(|fonts| return fonts.get_array(0).ok().flatten())
+    } else {
+        Some(font.clone())
+    };
+
+    return descriptor_owner
+        .and_then(|owner| return owner.get_dict("FontDescriptor").ok().flatten())
+        .is_some_and(|descriptor| {
+            return ["FontFile", "FontFile2", "FontFile3"]
+                .iter()
+                .any(|key| return descriptor.get_dict(key).ok().flatten().is_some());
+        });
+}
+
+fn has_transparency(state: &PdfObject) -> bool {
+    let below_one = |key: &str| {
+        return state
+            .get_dict(key)
+            .ok()
+            .flatten()
+            .and_then(|value| return value.as_float().ok())
+            .is_some_and(|alpha| return alpha < 1.0);
+    };
+    let soft_mask = name_of(state, "SMask").map_or_else(
+        || return state.get_dict("SMask").ok().flatten().is_some(),
+        |name| return name != "None",
+    );
+    let blends =
+        name_of(state, "BM").is_some_and(|mode| return mode != "Normal" && mode != "Compatible");
+
+    return below_one("CA") || below_one("ca") || soft_mask || blends;
+}
+
+/// Looks through a resource dictionary, and those of the forms it uses, returning the largest
+/// image resolution found and whether anything is transparent
+fn scan_resources(
+    resources: &PdfObject,
+    page_size: (f32, f32),
+    fonts: &mut BTreeSet<String>,
+    depth: u8,
+) -> (f32, bool) {
+    let mut max_dpi: f32 = 0.0;
+    let mut transparent = dict_values(resources, "ExtGState")
+        .iter()
+        .any(has_transparency);
+
+    for font in dict_values(resources, "Font") {
+        if !is_embedded(&font) {
+            fonts
+                .insert(name_of(&font, "BaseFont").unwrap_or_else(|| return "Unnamed".to_string()));
+        }
+    }
+
+    for xobject in dict_values(resources, "XObject") {
+        match name_of(&xobject, "Subtype").as_deref() {
+            Some("Image") => {
+                let dimension = |key: &str| {
+                    return xobject
+                        .get_dict(key)
+                        .ok()
+                        .flatten()
+                        .and_then(|value| return value.as_int().ok())
+                        .unwrap_or(0);
+                };
+                // Drawn no larger than the page, so this is the lowest it can print at
+                #[allow(clippy::cast_precision_loss)]
+                let dpi = (dimension("Width") as f32 / (page_size.0 / 72.0))
+                    .max(dimension("Height") as f32 / (page_size.1 / 72.0));
+                max_dpi = max_dpi.max(dpi);
+                transparent |= xobject.get_dict("SMask").ok().flatten().is_some();
+            }
+            Some("Form") if depth > 0 => {
+                transparent |= xobject
+                    .get_dict("Group")
+                    .ok()
+                    .flatten()
+                    .is_some_and(|group| {
+                        return name_of(&group, "S").as_deref() == Some("Transparency");
+                    });
+                if let Ok(Some(form_resources)) = xobject.get_dict("Resources") {
+                    let (dpi, form_transparent) =
+                        scan_resources(&form_resources, page_size, fonts, depth - 1);
+                    max_dpi = max_dpi.max(dpi);
+                    transparent |= form_transparent;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    return (max_dpi, transparent);
+}
+
+fn inspect_pages(document: &PdfDocument) -> Result<(PageFindings, Vec<(i32, i32)>), String> {
+    let mut findings = PageFindings::default();
+    let mut sizes = vec![];
+    let page_count = document.page_count().map_err(|e| return e.to_string())?;
+
+    for index in 0..page_count {
+        let number = index + 1;
+        let bounds = document
+            .load_page(index)
+            .and_then(|page| return page.bounds())
+            .map_err(|e| return e.to_string())?;
+        let page_size = (bounds.x1 - bounds.x0, bounds.y1 - bounds.y0);
+        #[allow(clippy::cast_possible_truncation)]
+        let (width, height) = (page_size.0.round() as i32, page_size.1.round() as i32);
+        // Landscape and portrait pages of the same paper are the same size
+        sizes.push((width.min(height), width.max(height)));
+
+        let page = document
+            .find_page(index)
+            .map_err(|e| return e.to_string())?;
+        let rotation = page
+            .get_dict("Rotate")
+            .ok()
+            .flatten()
+            .and_then(|rotate| return rotate.as_int().ok())
+            .unwrap_or(0);
+        if rotation % 360 != 0 {
+            findings.rotated.push(number);
+        }
+
+        let mut transparent = page.get_dict("Group").ok().flatten().is_some_and(|group| {
+            return name_of(&group, "S").as_deref() == Some("Transparency");
+        });
+        if let Ok(Some(resources)) = page.get_dict("Resources") {
+            let (dpi, resources_transparent) = scan_resources(
+                &resources,
+                page_size,
+                &mut findings.fonts,
+                MAX_RESOURCE_DEPTH,
+            );
+            if dpi >= HIGH_RESOLUTION_DPI {
+                findings.high_resolution.push(number);
+                findings.max_dpi = findings.max_dpi.max(dpi);
+            }
+            transparent |= resources_transparent;
+        }
+        if transparent {
+            findings.transparency.push(number);
+        }
+    }
+
+    return Ok((findings, sizes));
+}
+
+/// Warnings about how a source file was opened, before its pages are looked at
+fn source_warnings(pdf_detail: &PdfPrintDetails, passwords: &Passwords) -> Vec<PreflightWarning> {
+    let path = Path::new(&pdf_detail.path);
+    if pdf::is_converted_path(path) {
+        return vec![];
+    }
+    let Ok(document) = PdfDocument::open(&pdf_detail.path) else {
+        return vec![];
+    };
+
+    let mut warnings = vec![];
+    if document.needs_password().unwrap_or(false) {
+        warnings.push(PreflightWarning::Encrypted {
+            document: pdf_detail.name.clone(),
+            locked: passwords.is_locked(path),
+        });
+    }
+    if document.was_repaired().unwrap_or(false) {
+        warnings.push(PreflightWarning::Repaired {
+            document: pdf_detail.name.clone(),
+        });
+    }
+
+    return warnings;
+}
+
+/// Checks the documents the way they would be combined, without printing them
+#[must_use]
+pub fn preflight(
+    pdfs: Vec<PdfPrintDetails>,
+    options: CombineOptions,
+    print_options: &PrintOptions,
+    passwords: &Passwords,
+) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    let mut sizes = HashSet::new();
+
+    for pdf_detail in pdfs {
+        let document = pdf_detail.name.clone();
+        report
+            .warnings
+            .extend(source_warnings(&pdf_detail, passwords));
+
+        let inspected = pdf::extract_document(pdf_detail, options, passwords)
+            .and_then(|extracted| return inspect_pages(&extracted));
+        let (findings, page_sizes) = match inspected {
+            Ok(inspected) => inspected,
+            Err(error) => {
+                report
+                    .warnings
+                    .push(PreflightWarning::Unreadable { document, error });
+                continue;
+            }
+        };
+
+        let pages = i32::try_from(page_sizes.len()).unwrap_or(i32::MAX);
+        // Combining pads each document to an even page count, with pages the printer also has
+        // to feed paper for
+        report.pages += pages + pages % 2;
+        sizes.extend(page_sizes);
+        if pages % 2 == 1 {
+            sizes.insert(PADDING_PAGE_SIZE);
+        }
+
+        if !findings.rotated.is_empty() {
+            report.warnings.push(PreflightWarning::RotatedPages {
+                document: document.clone(),
+                pages: findings.rotated,
+            });
+        }
+        if !findings.fonts.is_empty() {
+            report.warnings.push(PreflightWarning::UnembeddedFonts {
+                document: document.clone(),
+                fonts: findings.fonts.into_iter().collect(),
+            });
+        }
+        if !findings.high_resolution.is_empty() {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            report
+                .warnings
+                .push(PreflightWarning::HighResolutionImages {
+                    document: document.clone(),
+                    pages: findings.high_resolution,
+                    dpi: findings.max_dpi as u32,
+                });
+        }
+        if !findings.transparency.is_empty() {
+            report.warnings.push(PreflightWarning::Transparency {
+                document,
+                pages: findings.transparency,
+            });
+        }
+    }
+
+    if sizes.len() > 1 {
+        let mut sizes: Vec<(i32, i32)> = sizes.into_iter().collect();
+        sizes.sort_unstable();
+        report.warnings.insert(
+            0,
+            PreflightWarning::MixedPageSizes {
+                sizes: sizes
+                    .into_iter()
+                    .map(|(width, height)| return format!("{width}x{height}"))
+                    .collect(),
+            },
+        );
+    }
+
+    let sheets_per_copy = if print_options.sides == Some(Sides::OneSided) {
+        report.pages
+    } else {
+        report.pages / 2
+    };
+    let copies = print_options
+        .copies
+        .and_then(|copies| return i32::try_from(copies).ok())
+        .unwrap_or(1);
+    report.sheets = sheets_per_copy.saturating_mul(copies);
+
+    return report;
+}
@@ -0,0 +1,143 @@
+use mupdf::pdf::PdfDocument;
+use printers::common::base::job::PrinterJobOptions;
+use tempfile::NamedTempFile;
+
+use crate::pdf;
+
+pub use printers::common::base::printer::Printer;
+
+const DEFAULT_JOB_NAME: &str = "Pet Print PDF Job";
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Sides {
+    OneSided,
+    TwoSidedLongEdge,
+    TwoSidedShortEdge,
+}
+
+/// Options handed to the printer with a job, anything unset uses the printer's defaults
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct PrintOptions {
+    pub copies: Option<u32>,
+    pub sides: Option<Sides>,
+    pub color: Option<bool>,
+    pub job_name: Option<String>,
+}
+
+impl PrintOptions {
+    fn raw_properties(&self) -> Vec<(&'static str, String)> {
+        let mut properties = vec![];
+
+        if let Some(copies) = self.copies {
+            properties.push(("copies", copies.to_string()));
+        }
+
+        if let Some(sides) = self.sides {
+            let value = match sides {
+                Sides::OneSided => "one-sided",
+                Sides::TwoSidedLongEdge => "two-sided-long-edge",
+                Sides::TwoSidedShortEdge => "two-sided-short-edge",
+            };
+            properties.push(("sides", value.to_string()));
+        }
+
+        if let Some(color) = self.color {
+            let value = if color { "color" } else { "monochrome" };
+            properties.push(("print-color-mode", value.to_string()));
+        }
+
+        return properties;
+    }
+}
+
+/// Looks up a printer by name, or the default printer when no name is given
+///
+/// # Errors
+///
+/// Returns an error when there is no printer by that name, or no default printer.
+pub fn find_printer(name: Option<&str>) -> Result<Printer, String> {
+    return match name {
+        Some(name) => printers::get_printer_by_name(name)
+            .ok_or_else(|| return format!("Could not find printer {name}")),
+        None => printers::get_default_printer()
+            .ok_or_else(|| return "Could not get default printer".to_string()),
+    };
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct PrinterInfo {
+    pub name: String,
+    pub is_default: bool,
+}
+
+/// Lists the printers known to the system
+#[must_use]
+pub fn list_printers() -> Vec<PrinterInfo> {
+    let default = printers::get_default_printer().map(|printer| return printer.name);
+
+    return printers::get_printers()
+        .into_iter()
+        .map(|printer| {
+            return PrinterInfo {
+                is_default: default.as_ref() == Some(&printer.name),
+                name: printer.name,
+            };
+        })
+        .collect();
+}
+
+/// Sends a document to a printer and returns the id of the created job
+///
+/// # Errors
+///
+/// Returns an error when the printer rejects the job.
+pub fn print_document(
+    printer: &Printer,
+    document: &PdfDocument,
+    options: &PrintOptions,
+) -> Result<u64, String> {
+    // Create a temporary file to then send to a printer
+    let file = NamedTempFile::with_suffix(".pdf").map_err(|e| return e.to_string())?;
+    let file_path = file.path().to_owned();
+    pdf::save_document(document, &file_path)?;
+
+    let raw_properties = options.raw_properties();
+    let raw_properties: Vec<(&str, &str)> = raw_properties
+        .iter()
+        .map(|(key, value)| return (*key, value.as_str()))
+        .collect();
+
+    let job = printer
+        .print_file(
+            &file_path.to_string_lossy(),
+            PrinterJobOptions {
+                name: Some(options.job_name.as_deref().unwrap_or(DEFAULT_JOB_NAME)),
+                raw_properties: &raw_properties,
+            },
+        )
+        .map_err(|e| return e.to_string())?;
+    // The spooler reads the file after we return, so it has to outlive this call
+    file.keep().map_err(|e| return e.to_string())?;
+
+    return Ok(job);
+}
+
+/// Whether the printer still has the job in its queue
+#[must_use]
+pub fn is_job_active(printer: &Printer, job: u64) -> bool {
+    return printer
+        .get_active_jobs()
+        .iter()
+        .any(|active_job| return active_job.id == job);
+}
+
+/// Takes a job back out of the printer's queue
+///
+/// # Errors
+///
+/// Returns an error when the spooler refuses, for instance because the job already printed.
+pub fn cancel_job(printer: &Printer, job: u64) -> Result<(), String> {
+    return printer.cancel_job(job).map_err(|e| return e.to_string());
+}
@@ -0,0 +1,76 @@
+/// Parses a 1-based page range such as `1-3,5,8-` into 0-based page indices, in the order
+/// given and repeating pages given more than once, the way a range picked in the app is printed.
+///
+/// A range without a start begins at the first page, one without an end runs to the last page.
+///
+/// # Errors
+///
+/// Returns an error when a page is not a number, is out of range or a range is malformed.
+pub fn parse_page_range(spec: &str, page_count: i32) -> Result<Vec<i32>, String> {
+    let parse_page = |value: &str, default: i32| -> Result<i32, String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(default);
+        }
+
+        let page = value
+            .parse::<i32>()
+            .map_err(|_| return format!("Invalid page number: {value}"))?;
+        if page < 1 || page > page_count {
+            return Err(format!("Page {page} is out of range (1-{page_count})"));
+        }
+
+        return Ok(page);
+    };
+
+    let mut pages = vec![];
+    for part in spec.split(',') {
+        if let Some((start, end)) = part.split_once('-') {
+            let start = parse_page(start, 1)?;
+            let end = parse_page(end, page_count)?;
+            if start > end {
+                return Err(format!("Invalid page range: {part}"));
+            }
+            pages.extend(start..=end);
+        } else if part.trim().is_empty() {
+            return Err(format!("Invalid page range: {spec}"));
+        } else {
+            pages.push(parse_page(part, 1)?);
+        }
+    }
+
+    return Ok(pages.into_iter().map(|page| return page - 1).collect());
+}
+
+/// Formats 0-based page indices as a compact 1-based range such as `1-3,5`, the reverse
+/// of [`parse_page_range`]. Pages keep their order, only ascending runs are joined.
+#[must_use]
+pub fn format_page_range(pages: &[i32]) -> String {
+    let mut runs: Vec<(i32, i32)> = vec![];
+    for &page in pages {
+        match runs.last_mut() {
+            Some((_, end)) if *end + 1 == page => *end = page,
+            _ => runs.push((page, page)),
+        }
+    }
+
+    return runs
+        .into_iter()
+        .map(|(start, end)| {
+            if start == end {
+                return format!("{}", start + 1);
+            }
+            return format!("{}-{}", start + 1, end + 1);
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+}
+
+/// Whether a string only holds range characters, used to tell `file.pdf:1-3` apart from a path
+#[must_use]
+pub fn looks_like_range(spec: &str) -> bool {
+    return !spec.is_empty()
+        && spec
+            .chars()
+            .all(|c| return c.is_ascii_digit() || c == ',' || c == '-' || c == ' ');
+}
@@ -0,0 +1,163 @@
+use mupdf::pdf::{PdfAnnotationType, PdfDocument, PdfPage};
+use mupdf::{Page, Rect, TextPageFlags};
+use regex::Regex;
+
+use crate::select;
+
+/// Content to remove from a document before it is printed or saved
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Redaction {
+    /// An area of one page, in points from its top left corner
+    Area {
+        /// 0-based page, after collating
+        page: i32,
+        x0: f32,
+        y0: f32,
+        x1: f32,
+        y1: f32,
+    },
+    /// Every occurrence of the text, or of a regular expression when `regex` is set, on every
+    /// page. Matches do not span lines.
+    Text {
+        pattern: String,
+        #[serde(default)]
+        regex: bool,
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+}
+
+/// Redactions with their expressions built, ready to be applied page by page
+pub struct Redactor {
+    areas: Vec<(i32, Rect)>,
+    matchers: Vec<Regex>,
+}
+
+/// Bounds of the characters of each line that match
+fn text_hits(page: &Page, matchers: &[Regex]) -> Result<Vec<Rect>, String> {
+    let text_page = page
+        .to_text_page(TextPageFlags::empty())
+        .map_err(|e| return e.to_string())?;
+    let mut hits = vec![];
+
+    for block in text_page.blocks() {
+        for line in block.lines() {
+            // Byte offset in the line text of each character, along with its bounds
+            let mut text = String::new();
+            let mut chars = vec![];
+            for text_char in line.chars() {
+                let Some(c) = text_char.char() else {
+                    continue;
+                };
+                chars.push((text.len(), Rect::from(text_char.quad())));
+                text.push(c);
+            }
+
+            for matcher in matchers {
+                for found in matcher.find_iter(&text) {
+                    let covered = chars
+                        .iter()
+                        .filter(|(offset, _)| return found.range().contains(offset))
+                        .map(|(_, bounds)| return *bounds);
+                    if let Some(bounds) = covered.reduce(|a, b| return a.union(&b)) {
+                        hits.push(bounds);
+                    }
+                }
+            }
+        }
+    }
+
+    return Ok(hits);
+}
+
+impl Redactor {
+    /// Builds the expressions of the text redactions
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when a text pattern is empty or not a valid regular expression.
+    pub fn new(redactions: &[Redaction]) -> Result<Self, String> {
+        let mut areas = vec![];
+        let mut matchers = vec![];
+
+        for redaction in redactions {
+            match redaction {
+                Redaction::Area {
+                    page,
+                    x0,
+                    y0,
+                    x1,
+                    y1,
+                } => areas.push((*page, Rect::new(*x0, *y0, *x1, *y1))),
+                Redaction::Text {
+                    pattern,
+                    regex,
+                    case_sensitive,
+                } => matchers.push(select::text_matcher(pattern, *regex, *case_sensitive)?),
+            }
+        }
+
+        return Ok(Self { areas, matchers });
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        return self.areas.is_empty() && self.matchers.is_empty();
+    }
+
+    /// Whether any of the redactions look for text
+    #[must_use]
+    pub fn has_text(&self) -> bool {
+        return !self.matchers.is_empty();
+    }
+
+    /// Removes the redacted text, graphics and image pixels from a page of `document`, which
+    /// was at `position` in its source after collating, returning how many text matches were
+    /// removed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the page can not be loaded, searched or redacted.
+    pub fn apply(
+        &self,
+        document: &PdfDocument,
+        index: i32,
+        position: i32,
+    ) -> Result<usize, String> {
+        let page = document
+            .load_page(index)
+            .map_err(|e| return e.to_string())?;
+
+        let mut rects: Vec<Rect> = self
+            .areas
+            .iter()
+            .filter(|(page, _)| return *page == position)
+            .map(|(_, rect)| return *rect)
+            .collect();
+        let hits = if self.matchers.is_empty() {
+            vec![]
+        } else {
+            text_hits(&page, &self.matchers)?
+        };
+        let matches = hits.len();
+        rects.extend(hits);
+        if rects.is_empty() {
+            return Ok(matches);
+        }
+
+        let mut page = PdfPage::try_from(page).map_err(|e| return e.to_string())?;
+        for rect in rects {
+            let mut annotation = page
+                .create_annotation(PdfAnnotationType::Redact)
+                .map_err(|e| return e.to_string())?;
+            annotation
+                .set_rect(rect)
+                .map_err(|e| return e.to_string())?;
+        }
+        // Unlike drawing boxes, this removes whatever the annotations cover from the page
+        page.redact().map_err(|e| return e.to_string())?;
+
+        return Ok(matches);
+    }
+}
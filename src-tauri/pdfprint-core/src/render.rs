@@ -0,0 +1,42 @@
+use mupdf::{Colorspace, ImageFormat, Matrix};
+
+use crate::passwords::Passwords;
+use crate::pdf::{self, CombineOptions, PdfPrintDetails};
+
+/// Renders a page of a document the way it would be printed, with its redactions applied, as a
+/// PNG image. `position` is the 0-based page after collating, numbered like print ranges and
+/// redaction areas, whether or not the print range or page selection keeps it.
+///
+/// # Errors
+///
+/// Returns an error when the document can not be combined, the page does not exist or can not be rendered.
+pub fn render_page_png(
+    mut pdf_detail: PdfPrintDetails,
+    position: i32,
+    dpi: f32,
+    mut options: CombineOptions,
+    passwords: &Passwords,
+) -> Result<Vec<u8>, String> {
+    pdf_detail.print_range = Some(vec![position]);
+    pdf_detail.select = None;
+    options.skip_blank_pages = None;
+    let document = pdf::extract_document(pdf_detail, options, passwords)?;
+    let page = document.load_page(0).map_err(|e| return e.to_string())?;
+
+    let scale = dpi / 72.0;
+    let pixmap = page
+        .to_pixmap(
+            &Matrix::new_scale(scale, scale),
+            &Colorspace::device_rgb(),
+            false,
+            true,
+        )
+        .map_err(|e| return e.to_string())?;
+
+    let mut png = vec![];
+    pixmap
+        .write_to(&mut png, ImageFormat::PNG)
+        .map_err(|e| return e.to_string())?;
+
+    return Ok(png);
+}
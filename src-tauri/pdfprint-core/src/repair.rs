@@ -0,0 +1,127 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use mupdf::pdf::{PdfDocument, PdfWriteOptions};
+
+use crate::passwords::Passwords;
+use crate::pdf;
+
+/// Shown for documents whose cross-reference table mupdf had to rebuild to open them
+pub const REBUILT_WARNING: &str =
+    "The cross-reference table was broken and has been rebuilt, pages may be missing or wrong";
+
+#[derive(serde::Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// Pages in the repaired copy
+    pub pages: i32,
+    /// What was wrong with the source, empty when it was not damaged
+    pub warnings: Vec<String>,
+    /// Copy of the damaged original, kept when it was repaired in place
+    pub backup: Option<String>,
+}
+
+/// What mupdf had to work around to open the document, which must already be authenticated.
+/// Pages are only loaded one by one when the document had to be rebuilt.
+///
+/// mupdf reports what it works around through the warning callback of its context, but the
+/// bindings keep that context to themselves and print the warnings to stderr, so they can not
+/// be collected per document. This rebuilds what can be asked of the document instead.
+#[must_use]
+pub fn damage_warnings(document: &PdfDocument) -> Vec<String> {
+    if !document.was_repaired().unwrap_or(false) {
+        return vec![];
+    }
+
+    let mut warnings = vec![REBUILT_WARNING.to_string()];
+    match document.page_count() {
+        Ok(page_count) => {
+            for index in 0..page_count {
+                if let Err(error) = document.load_page(index) {
+                    warnings.push(format!("Page {} could not be read: {error}", index + 1));
+                }
+            }
+        }
+        Err(error) => warnings.push(format!("The pages could not be counted: {error}")),
+    }
+
+    return warnings;
+}
+
+/// Copies the original next to itself before it is replaced, named like the backups of corrupt
+/// settings files
+fn backup_original(path: &Path) -> Result<PathBuf, String> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| return duration.as_secs());
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| return format!("No file name for {}", path.to_string_lossy()))?
+        .to_string_lossy();
+    let backup = path.with_file_name(format!("{file_name}.damaged-{timestamp}"));
+
+    fs::copy(path, &backup).map_err(|e| return e.to_string())?;
+
+    return Ok(backup);
+}
+
+/// Writes a clean copy of a damaged PDF to `destination`, which may be the source itself, in
+/// which case the original is backed up first. Unused and duplicate objects are dropped and
+/// every object is written out again.
+///
+/// # Errors
+///
+/// Returns an error when the source can not be opened, the copy can not be written or is still damaged.
+pub fn repair_pdf(
+    source: &Path,
+    destination: &Path,
+    passwords: &Passwords,
+) -> Result<RepairReport, String> {
+    let document = pdf::open_pdf(source, passwords)?;
+    let warnings = damage_warnings(&document);
+    let pages = document.page_count().map_err(|e| return e.to_string())?;
+
+    let mut options = PdfWriteOptions::default();
+    options
+        .set_garbage_level(4)
+        .set_clean(true)
+        .set_compress(true);
+    // mupdf reads the source lazily, so it can not be overwritten while it is still open
+    let partial = destination.with_extension("repairing.pdf");
+    let in_place = fs::canonicalize(destination).ok() == fs::canonicalize(source).ok();
+    let saved = document
+        .save_with_options(&partial.to_string_lossy(), options)
+        .map_err(|e| return e.to_string());
+    drop(document);
+    let replaced = saved.and_then(|()| {
+        let backup = if in_place {
+            Some(backup_original(destination)?)
+        } else {
+            None
+        };
+        fs::rename(&partial, destination).map_err(|e| return e.to_string())?;
+        return Ok(backup);
+    });
+    let backup = match replaced {
+        Ok(backup) => backup,
+        Err(error) => {
+            let _ = fs::remove_file(&partial);
+            return Err(error);
+        }
+    };
+
+    let repaired =
+        PdfDocument::open(&destination.to_string_lossy()).map_err(|e| return e.to_string())?;
+    if repaired.was_repaired().unwrap_or(false) {
+        return Err(format!(
+            "{} is still damaged after repairing",
+            destination.to_string_lossy()
+        ));
+    }
+
+    return Ok(RepairReport {
+        pages,
+        warnings,
+        backup: backup.map(|backup| return backup.to_string_lossy().to_string()),
+    });
+}
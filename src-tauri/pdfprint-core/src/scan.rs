@@ -0,0 +1,200 @@
+use std::fs::{read_dir, Metadata};
+use std::path::Path;
+
+use log::info;
+
+use crate::entry_id::EntryIds;
+use crate::metadata::{self, DocumentMetadata};
+use crate::passwords::Passwords;
+use crate::pdf::{self, PdfDocument};
+use crate::repair;
+use crate::scan_filter::{Accepted, ScanFilter};
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    Pdf,
+    Image,
+    /// Plain text, laid out on pages when combined
+    Text,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct PdfDetails {
+    pub name: String,
+    pub path: String,
+    pub pages: i32,
+    pub size: u64,
+    pub parent: Option<u64>,
+    pub id: u64,
+    pub format: DocumentFormat,
+    /// Needs a password to open
+    pub encrypted: bool,
+    /// Encrypted and not unlocked yet this session, its pages are unknown until it is
+    pub locked: bool,
+    /// Left empty for images, text and locked documents
+    pub metadata: DocumentMetadata,
+    /// Could not be opened, or only after mupdf rebuilt it, see `warnings`
+    pub damaged: bool,
+    /// What was wrong when opening it
+    pub warnings: Vec<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct DirEntry {
+    pub name: String,
+    pub parent: Option<u64>,
+    pub path: String,
+    pub id: u64,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(tag = "type")]
+pub enum Entry {
+    #[serde(rename = "pdf")]
+    PdfDetails(PdfDetails),
+    #[serde(rename = "dir")]
+    DirEntry(DirEntry),
+}
+
+/// What can be read from a PDF that may need a password
+struct Inspected {
+    pages: i32,
+    encrypted: bool,
+    locked: bool,
+    metadata: DocumentMetadata,
+    warnings: Vec<String>,
+}
+
+/// Counts the pages of a PDF, reads its metadata and checks for damage, unless it is locked
+fn inspect_pdf(path: &Path, passwords: &Passwords) -> Result<Inspected, String> {
+    let mut document =
+        PdfDocument::open(&path.to_string_lossy()).map_err(|e| return e.to_string())?;
+    let encrypted = document
+        .needs_password()
+        .map_err(|e| return e.to_string())?;
+    if passwords.authenticate(&mut document, path).is_err() {
+        // Pages can not be loaded yet to check them one by one
+        let warnings = if document.was_repaired().unwrap_or(false) {
+            vec![repair::REBUILT_WARNING.to_string()]
+        } else {
+            vec![]
+        };
+        return Ok(Inspected {
+            pages: 0,
+            encrypted,
+            locked: true,
+            metadata: DocumentMetadata::default(),
+            warnings,
+        });
+    }
+
+    return Ok(Inspected {
+        pages: document.page_count().map_err(|e| return e.to_string())?,
+        encrypted,
+        locked: false,
+        metadata: metadata::read_metadata(&document),
+        warnings: repair::damage_warnings(&document),
+    });
+}
+
+/// Builds the listing details for a single document, counting its pages
+///
+/// # Errors
+///
+/// Returns an error when an image or text document can not be opened.
+pub fn document_details(
+    path: &Path,
+    metadata: &Metadata,
+    parent: Option<u64>,
+    ids: &mut EntryIds,
+    passwords: &Passwords,
+) -> Result<PdfDetails, String> {
+    let format = if pdf::is_image_path(path) {
+        DocumentFormat::Image
+    } else if pdf::is_text_path(path) {
+        DocumentFormat::Text
+    } else {
+        DocumentFormat::Pdf
+    };
+    let inspected = match format {
+        // Still listed so it can be repaired, rather than silently left out
+        DocumentFormat::Pdf => inspect_pdf(path, passwords).unwrap_or_else(|error| {
+            return Inspected {
+                pages: 0,
+                encrypted: false,
+                locked: false,
+                metadata: DocumentMetadata::default(),
+                warnings: vec![error],
+            };
+        }),
+        DocumentFormat::Image | DocumentFormat::Text => Inspected {
+            pages: pdf::count_pages(path, passwords)?,
+            encrypted: false,
+            locked: false,
+            metadata: DocumentMetadata::default(),
+            warnings: vec![],
+        },
+    };
+
+    return Ok(PdfDetails {
+        name: path.file_name().map_or_else(String::new, |name| {
+            return name.to_string_lossy().to_string();
+        }),
+        path: path.to_string_lossy().to_string(),
+        pages: inspected.pages,
+        size: metadata.len(),
+        parent,
+        id: ids.id_for(path),
+        format,
+        encrypted: inspected.encrypted,
+        locked: inspected.locked,
+        metadata: inspected.metadata,
+        damaged: !inspected.warnings.is_empty(),
+        warnings: inspected.warnings,
+    });
+}
+
+/// Lists the sub folders and documents directly inside `path` that pass the filter
+///
+/// # Errors
+///
+/// Returns an error when the folder can not be read.
+pub fn scan_folder(
+    path: &Path,
+    filter: &ScanFilter,
+    ids: &mut EntryIds,
+    passwords: &Passwords,
+) -> Result<Vec<Entry>, String> {
+    let parent = ids.id_for(path);
+    let entries: std::fs::ReadDir = read_dir(path).map_err(|e| return e.to_string())?;
+
+    info!("Processing folder: {}", path.to_string_lossy());
+
+    let pdfs: Vec<Entry> = entries
+        .filter_map(|entry: Result<std::fs::DirEntry, std::io::Error>| {
+            let dir_entry = entry.ok()?;
+            let accepted = filter.accept(&dir_entry)?;
+
+            let metadata = match accepted {
+                Accepted::Dir => {
+                    return Some(Entry::DirEntry(DirEntry {
+                        name: dir_entry.file_name().to_string_lossy().to_string(),
+                        parent: Some(parent),
+                        path: dir_entry.path().to_string_lossy().to_string(),
+                        id: ids.id_for(&dir_entry.path()),
+                    }));
+                }
+                Accepted::File(metadata) => metadata,
+            };
+
+            return document_details(&dir_entry.path(), &metadata, Some(parent), ids, passwords)
+                .ok()
+                .map(Entry::PdfDetails);
+        })
+        .collect();
+
+    info!("Found {} pdfs in folder", pdfs.len());
+
+    return Ok(pdfs);
+}
@@ -0,0 +1,155 @@
+use std::fs::{DirEntry, Metadata};
+use std::path::Path;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use log::warn;
+
+use crate::file_utils;
+use crate::pdf::{IMAGE_EXTENSIONS, TEXT_EXTENSIONS};
+
+/// Settings controlling which entries [`crate::scan::scan_folder`] lists
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct ScanFilters {
+    /// File extensions to include, compared case-insensitively
+    pub extensions: Vec<String>,
+    /// Directory extensions that are really document bundles and should not be listed
+    pub skip_bundles: Vec<String>,
+    pub include_hidden: bool,
+    /// `.gitignore` style patterns, relative to the workspace root
+    pub ignore: Vec<String>,
+    /// Files larger than this many bytes are skipped
+    pub max_file_size: Option<u64>,
+    pub follow_symlinks: bool,
+}
+
+impl Default for ScanFilters {
+    fn default() -> Self {
+        return Self {
+            extensions: std::iter::once("pdf")
+                .chain(IMAGE_EXTENSIONS)
+                .chain(TEXT_EXTENSIONS)
+                .map(str::to_string)
+                .collect(),
+            skip_bundles: vec![
+                "pages".to_string(),
+                "numbers".to_string(),
+                "key".to_string(),
+            ],
+            include_hidden: false,
+            ignore: vec![],
+            max_file_size: None,
+            follow_symlinks: false,
+        };
+    }
+}
+
+pub enum Accepted {
+    Dir,
+    File(Metadata),
+}
+
+/// Compiled form of [`ScanFilters`] for a given workspace root
+pub struct ScanFilter {
+    filters: ScanFilters,
+    gitignore: Gitignore,
+}
+
+fn normalize_extension(extension: &str) -> String {
+    return extension.trim().trim_start_matches('.').to_lowercase();
+}
+
+fn matches_extension(name: &str, extensions: &[String]) -> bool {
+    return file_utils::get_extension_from_filename(name)
+        .is_some_and(|ext| return extensions.contains(&ext.to_lowercase()));
+}
+
+#[cfg(windows)]
+fn is_hidden(name: &str, metadata: &Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+
+    return name.starts_with('.') || metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0;
+}
+
+#[cfg(not(windows))]
+fn is_hidden(name: &str, _metadata: &Metadata) -> bool {
+    return name.starts_with('.');
+}
+
+impl ScanFilter {
+    #[must_use]
+    pub fn new(root: &Path, filters: ScanFilters) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in &filters.ignore {
+            if let Err(err) = builder.add_line(None, pattern) {
+                warn!("Skipping invalid ignore pattern {pattern}: {err}");
+            }
+        }
+        let gitignore = builder.build().unwrap_or_else(|err| {
+            warn!("Could not build ignore patterns: {err}");
+            return Gitignore::empty();
+        });
+
+        let filters = ScanFilters {
+            extensions: filters
+                .extensions
+                .iter()
+                .map(|ext| return normalize_extension(ext))
+                .collect(),
+            skip_bundles: filters
+                .skip_bundles
+                .iter()
+                .map(|ext| return normalize_extension(ext))
+                .collect(),
+            ..filters
+        };
+
+        return Self { filters, gitignore };
+    }
+
+    /// Decides whether a directory entry should be listed
+    #[must_use]
+    pub fn accept(&self, entry: &DirEntry) -> Option<Accepted> {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        let file_type = entry.file_type().ok()?;
+
+        if file_type.is_symlink() && !self.filters.follow_symlinks {
+            return None;
+        }
+
+        // `DirEntry::metadata` does not traverse symlinks, `fs::metadata` does
+        let metadata = std::fs::metadata(&path).ok()?;
+
+        if !self.filters.include_hidden && is_hidden(&name, &metadata) {
+            return None;
+        }
+
+        if self.gitignore.matched(&path, metadata.is_dir()).is_ignore() {
+            return None;
+        }
+
+        if metadata.is_dir() {
+            if matches_extension(&name, &self.filters.skip_bundles) {
+                return None;
+            }
+            return Some(Accepted::Dir);
+        }
+
+        if !matches_extension(&name, &self.filters.extensions) {
+            return None;
+        }
+
+        if self
+            .filters
+            .max_file_size
+            .is_some_and(|max| return metadata.len() > max)
+        {
+            return None;
+        }
+
+        return Some(Accepted::File(metadata));
+    }
+}
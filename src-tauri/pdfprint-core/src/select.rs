@@ -0,0 +1,65 @@
+use std::collections::BTreeSet;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::text_index;
+
+/// Picks pages by their text rather than by number
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PageSelector {
+    /// Text to look for, or a regular expression when `regex` is set
+    pub pattern: String,
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Pages before each match to include as well
+    #[serde(default)]
+    pub before: usize,
+    /// Pages after each match to include as well, such as the rest of a two page form
+    #[serde(default)]
+    pub after: usize,
+}
+
+/// Builds the expression text is searched with, escaping `pattern` unless it is a regex
+///
+/// # Errors
+///
+/// Returns an error when the pattern is empty or not a valid regular expression.
+pub fn text_matcher(pattern: &str, regex: bool, case_sensitive: bool) -> Result<Regex, String> {
+    let pattern = if regex {
+        pattern.to_string()
+    } else {
+        // Page text has its whitespace collapsed, so the text looked for needs the same
+        regex::escape(&text_index::collapse_whitespace(pattern))
+    };
+    if pattern.is_empty() {
+        return Err("Nothing to look for".to_string());
+    }
+
+    return RegexBuilder::new(&pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+        .map_err(|e| return e.to_string());
+}
+
+impl PageSelector {
+    /// Positions of the pages to keep, in order, given the text of each page
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the pattern is empty or not a valid regular expression.
+    pub fn select(&self, texts: &[String]) -> Result<Vec<usize>, String> {
+        let matcher = text_matcher(&self.pattern, self.regex, self.case_sensitive)?;
+        let mut selected = BTreeSet::new();
+
+        for (index, text) in texts.iter().enumerate() {
+            if matcher.is_match(text) {
+                let last = (index + self.after).min(texts.len() - 1);
+                selected.extend(index.saturating_sub(self.before)..=last);
+            }
+        }
+
+        return Ok(selected.into_iter().collect());
+    }
+}
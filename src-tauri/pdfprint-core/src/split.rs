@@ -0,0 +1,240 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::blank::{self, BlankOptions};
+use crate::naming;
+use crate::passwords::Passwords;
+use crate::pdf::{self, CombineOptions, PdfDocument, PdfPrintDetails};
+use crate::range;
+
+/// Template used when none is given, `records-1.pdf` and so on
+pub const DEFAULT_NAME_TEMPLATE: &str = "{name}-{index}";
+
+/// Where a document is cut into parts
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SplitMode {
+    /// One part per 1-based range such as `1-3,5`
+    Ranges { ranges: Vec<String> },
+    /// One part per top-level bookmark, running up to the next one
+    Bookmarks,
+    /// Parts of a fixed number of pages, the last one may be shorter
+    EveryN { pages: i32 },
+    /// Parts between blank pages, which are left out
+    BlankSeparators {
+        #[serde(default)]
+        blank: BlankOptions,
+    },
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct SplitOptions {
+    #[serde(flatten)]
+    pub mode: SplitMode,
+    /// File name template, with `{index}`, `{name}`, `{title}`, `{pages}` and `{range}` placeholders
+    #[serde(default = "default_name_template")]
+    pub name_template: String,
+}
+
+fn default_name_template() -> String {
+    return DEFAULT_NAME_TEMPLATE.to_string();
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct SplitPart {
+    /// Name of the written file
+    pub file: String,
+    /// 1-based pages of the source in the part
+    pub range: String,
+    pub pages: usize,
+    /// Bookmark the part starts at
+    pub title: Option<String>,
+}
+
+/// Pages of the source making up one output file
+struct Section {
+    pages: Vec<i32>,
+    title: Option<String>,
+}
+
+fn by_ranges(ranges: &[String], page_count: i32) -> Result<Vec<Section>, String> {
+    return ranges
+        .iter()
+        .map(|spec| -> Result<Section, String> {
+            return Ok(Section {
+                pages: range::parse_page_range(spec, page_count)?,
+                title: None,
+            });
+        })
+        .collect();
+}
+
+fn every_n(pages: i32, page_count: i32) -> Result<Vec<Section>, String> {
+    if pages < 1 {
+        return Err("Parts need at least one page".to_string());
+    }
+
+    return Ok((0..page_count)
+        .step_by(usize::try_from(pages).map_err(|e| return e.to_string())?)
+        .map(|start| {
+            return Section {
+                pages: (start..(start + pages).min(page_count)).collect(),
+                title: None,
+            };
+        })
+        .collect());
+}
+
+fn by_bookmarks(document: &PdfDocument, page_count: i32) -> Result<Vec<Section>, String> {
+    let mut starts: Vec<(i32, String)> = document
+        .outlines()
+        .map_err(|e| return e.to_string())?
+        .into_iter()
+        .filter_map(|outline| {
+            let page = i32::try_from(outline.page?).ok()?;
+            return Some((page, outline.title));
+        })
+        .filter(|(page, _)| return *page < page_count)
+        .collect();
+    if starts.is_empty() {
+        return Err("The document has no bookmarks".to_string());
+    }
+    starts.sort_by_key(|(page, _)| return *page);
+    // Bookmarks sharing a page would make empty parts, keep the first
+    starts.dedup_by_key(|(page, _)| return *page);
+
+    let mut sections = vec![];
+    // Pages before the first bookmark, such as a cover page
+    if starts[0].0 > 0 {
+        sections.push(Section {
+            pages: (0..starts[0].0).collect(),
+            title: None,
+        });
+    }
+    for (index, (start, title)) in starts.iter().enumerate() {
+        let end = starts
+            .get(index + 1)
+            .map_or(page_count, |(next, _)| return *next);
+        sections.push(Section {
+            pages: (*start..end).collect(),
+            title: Some(title.clone()),
+        });
+    }
+
+    return Ok(sections);
+}
+
+fn by_blank_separators(
+    document: &PdfDocument,
+    page_count: i32,
+    options: BlankOptions,
+) -> Result<Vec<Section>, String> {
+    let mut sections = vec![];
+    let mut current = vec![];
+
+    for index in 0..page_count {
+        let page = document
+            .load_page(index)
+            .map_err(|e| return e.to_string())?;
+        if blank::is_blank(&page, options)? {
+            if !current.is_empty() {
+                sections.push(Section {
+                    pages: std::mem::take(&mut current),
+                    title: None,
+                });
+            }
+        } else {
+            current.push(index);
+        }
+    }
+    if !current.is_empty() {
+        sections.push(Section {
+            pages: current,
+            title: None,
+        });
+    }
+
+    return Ok(sections);
+}
+
+/// Splits a document into parts written to `destination`, never replacing files already there
+///
+/// # Errors
+///
+/// Returns an error when the document can not be opened, a range is invalid or a part can not be written.
+pub fn split_document(
+    source: &Path,
+    destination: &Path,
+    options: &SplitOptions,
+    passwords: &Passwords,
+) -> Result<Vec<SplitPart>, String> {
+    let document = pdf::open_pdf(source, passwords)?;
+    let page_count = document.page_count().map_err(|e| return e.to_string())?;
+
+    let sections = match &options.mode {
+        SplitMode::Ranges { ranges } => by_ranges(ranges, page_count)?,
+        SplitMode::Bookmarks => by_bookmarks(&document, page_count)?,
+        SplitMode::EveryN { pages } => every_n(*pages, page_count)?,
+        SplitMode::BlankSeparators { blank } => by_blank_separators(&document, page_count, *blank)?,
+    };
+    if sections.is_empty() {
+        return Err("Nothing to split".to_string());
+    }
+
+    std::fs::create_dir_all(destination).map_err(|e| return e.to_string())?;
+    let mut taken: HashSet<String> = std::fs::read_dir(destination)
+        .map_err(|e| return e.to_string())?
+        .filter_map(Result::ok)
+        .map(|entry| return entry.file_name().to_string_lossy().to_lowercase())
+        .collect();
+
+    let source_path = source.to_string_lossy().to_string();
+    let name = naming::file_stem(&source_path);
+    let count = sections.len();
+
+    return sections
+        .into_iter()
+        .enumerate()
+        .map(|(index, section)| -> Result<SplitPart, String> {
+            let range = range::format_page_range(&section.pages);
+            let pages = section.pages.len();
+            let file = naming::pdf_file_name(
+                &options.name_template,
+                &[
+                    ("index", naming::padded_index(index + 1, count)),
+                    ("name", name.clone()),
+                    (
+                        "title",
+                        section.title.clone().unwrap_or_else(|| return name.clone()),
+                    ),
+                    ("pages", pages.to_string()),
+                    ("range", range.clone()),
+                ],
+                &mut taken,
+            );
+
+            let part = pdf::extract_document(
+                PdfPrintDetails {
+                    name: name.clone(),
+                    pages: page_count,
+                    size: 0,
+                    path: source_path.clone(),
+                    print_range: Some(section.pages),
+                    collate: None,
+                    select: None,
+                    redactions: vec![],
+                },
+                CombineOptions::default(),
+                passwords,
+            )?;
+            pdf::save_document(&part, &destination.join(&file))?;
+
+            return Ok(SplitPart {
+                file,
+                range,
+                pages,
+                title: section.title,
+            });
+        })
+        .collect();
+}
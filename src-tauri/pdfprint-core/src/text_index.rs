@@ -0,0 +1,283 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{read_dir, Metadata};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use log::warn;
+use mupdf::{Page, TextPageFlags};
+
+use crate::passwords::Passwords;
+use crate::pdf::{self, ImagePageSize};
+use crate::scan_filter::{Accepted, ScanFilter};
+
+/// Characters of context shown on each side of a match
+const SNIPPET_CONTEXT: usize = 40;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IndexedDocument {
+    /// Modification time in milliseconds since the Unix epoch, used to spot changed files
+    pub modified: u64,
+    pub size: u64,
+    /// Text of each page, with runs of whitespace collapsed to a single space
+    pub pages: Vec<String>,
+    /// Password protected, so its text is never written out with the index
+    #[serde(skip)]
+    pub encrypted: bool,
+}
+
+/// Text of the documents in one or more workspaces, keyed by path
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TextIndex {
+    #[serde(serialize_with = "serialize_unencrypted")]
+    pub documents: BTreeMap<String, IndexedDocument>,
+}
+
+/// Leaves out encrypted documents, whose text only lives in memory for as long as their
+/// password does
+fn serialize_unencrypted<S: serde::Serializer>(
+    documents: &BTreeMap<String, IndexedDocument>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    return serializer.collect_map(
+        documents
+            .iter()
+            .filter(|(_, document)| return !document.encrypted),
+    );
+}
+
+#[derive(serde::Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexUpdate {
+    /// Documents that were new or had changed
+    pub indexed: usize,
+    /// Documents that were deleted or are now filtered out
+    pub removed: usize,
+    /// Documents whose text could not be extracted
+    pub failed: usize,
+}
+
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    pub path: String,
+    pub name: String,
+    /// 1-based page the match is on
+    pub page: i32,
+    pub snippet: String,
+}
+
+fn modified_millis(metadata: &Metadata) -> u64 {
+    return metadata
+        .modified()
+        .ok()
+        .and_then(|modified| return modified.duration_since(UNIX_EPOCH).ok())
+        .and_then(|duration| return u64::try_from(duration.as_millis()).ok())
+        .unwrap_or_default();
+}
+
+/// Joins words with single spaces, so line breaks and spacing in the PDF do not matter
+#[must_use]
+pub fn collapse_whitespace(text: &str) -> String {
+    return text.split_whitespace().collect::<Vec<&str>>().join(" ");
+}
+
+/// Text of a single page, with runs of whitespace collapsed to a single space
+///
+/// # Errors
+///
+/// Returns an error when the text of the page can not be extracted.
+pub fn page_text(page: &Page) -> Result<String, String> {
+    let text = page
+        .to_text_page(TextPageFlags::empty())
+        .and_then(|text_page| return text_page.to_text())
+        .map_err(|e| return e.to_string())?;
+
+    return Ok(collapse_whitespace(&text));
+}
+
+/// Extracts the text of each page of a PDF or text document, text laid out on the default
+/// page size. Images have no text and get no pages.
+///
+/// # Errors
+///
+/// Returns an error when the document can not be opened or the text of a page extracted.
+pub fn extract_text(path: &Path, passwords: &Passwords) -> Result<Vec<String>, String> {
+    if pdf::is_image_path(path) {
+        return Ok(vec![]);
+    }
+
+    let document = if pdf::is_text_path(path) {
+        pdf::open_text(path, ImagePageSize::default())?
+    } else {
+        pdf::open_document(path, passwords)?
+    };
+    let page_count = document.page_count().map_err(|e| return e.to_string())?;
+
+    return (0..page_count)
+        .map(|index| -> Result<String, String> {
+            let page = document
+                .load_page(index)
+                .map_err(|e| return e.to_string())?;
+            return page_text(&page);
+        })
+        .collect();
+}
+
+/// Every document under `root` that passes the filter, including those in sub folders
+fn workspace_documents(root: &Path, filter: &ScanFilter) -> Vec<(PathBuf, Metadata)> {
+    let mut documents = vec![];
+    let mut folders = vec![root.to_path_buf()];
+    // Followed symlinks can lead back to a folder already walked
+    let mut visited = HashSet::new();
+
+    while let Some(folder) = folders.pop() {
+        if !visited.insert(
+            folder
+                .canonicalize()
+                .unwrap_or_else(|_| return folder.clone()),
+        ) {
+            continue;
+        }
+        let entries = match read_dir(&folder) {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!("Could not index {}: {err}", folder.to_string_lossy());
+                continue;
+            }
+        };
+
+        for entry in entries.filter_map(Result::ok) {
+            match filter.accept(&entry) {
+                Some(Accepted::Dir) => folders.push(entry.path()),
+                Some(Accepted::File(metadata)) => documents.push((entry.path(), metadata)),
+                None => {}
+            }
+        }
+    }
+
+    return documents;
+}
+
+/// Lowercases character by character, so indices line up with the original text
+fn fold(text: &str) -> Vec<char> {
+    return text
+        .chars()
+        .map(|c| return c.to_lowercase().next().unwrap_or(c))
+        .collect();
+}
+
+fn find(haystack: &[char], needle: &[char]) -> Option<usize> {
+    return haystack
+        .windows(needle.len())
+        .position(|window| return window == needle);
+}
+
+fn snippet(text: &[char], start: usize, end: usize) -> String {
+    let from = start.saturating_sub(SNIPPET_CONTEXT);
+    let to = (end + SNIPPET_CONTEXT).min(text.len());
+
+    let mut snippet = String::new();
+    if from > 0 {
+        snippet.push('…');
+    }
+    snippet.extend(&text[from..to]);
+    if to < text.len() {
+        snippet.push('…');
+    }
+
+    return snippet;
+}
+
+impl TextIndex {
+    /// Brings the documents under `root` up to date, extracting text only from files that are
+    /// new or whose size or modification time changed, and dropping those no longer there
+    pub fn update(
+        &mut self,
+        root: &Path,
+        filter: &ScanFilter,
+        passwords: &Passwords,
+    ) -> IndexUpdate {
+        let mut update = IndexUpdate::default();
+        let documents = workspace_documents(root, filter);
+
+        let present: HashSet<String> = documents
+            .iter()
+            .map(|(path, _)| return path.to_string_lossy().to_string())
+            .collect();
+        let before = self.documents.len();
+        self.documents.retain(|path, _| {
+            return !Path::new(path).starts_with(root) || present.contains(path);
+        });
+        update.removed = before - self.documents.len();
+
+        for (path, metadata) in documents {
+            let key = path.to_string_lossy().to_string();
+            let modified = modified_millis(&metadata);
+            let size = metadata.len();
+            if self
+                .documents
+                .get(&key)
+                .is_some_and(|indexed| return indexed.modified == modified && indexed.size == size)
+            {
+                continue;
+            }
+
+            // Indexed once unlocked, rather than kept without text until the file changes
+            if passwords.is_locked(&path) {
+                continue;
+            }
+            let encrypted = Passwords::is_encrypted(&path);
+            // Unreadable files are kept without text so they are not retried until they change
+            let pages = extract_text(&path, passwords).unwrap_or_else(|err| {
+                warn!("Could not extract text from {key}: {err}");
+                update.failed += 1;
+                return vec![];
+            });
+            self.documents.insert(
+                key,
+                IndexedDocument {
+                    modified,
+                    size,
+                    pages,
+                    encrypted,
+                },
+            );
+            update.indexed += 1;
+        }
+
+        return update;
+    }
+
+    /// Finds the pages of documents under `root` containing `query`, ignoring case and
+    /// differences in whitespace. Returns at most `limit` hits, one per page.
+    #[must_use]
+    pub fn search(&self, root: &Path, query: &str, limit: usize) -> Vec<SearchHit> {
+        let needle = fold(&collapse_whitespace(query));
+        if needle.is_empty() {
+            return vec![];
+        }
+
+        return self
+            .documents
+            .iter()
+            .filter(|(path, _)| return Path::new(path).starts_with(root))
+            .flat_map(|(path, document)| {
+                return document.pages.iter().zip(1..).filter_map(|(text, page)| {
+                    let start = find(&fold(text), &needle)?;
+                    let text: Vec<char> = text.chars().collect();
+
+                    return Some(SearchHit {
+                        path: path.clone(),
+                        name: Path::new(path)
+                            .file_name()
+                            .map_or_else(String::new, |name| {
+                                return name.to_string_lossy().to_string();
+                            }),
+                        page,
+                        snippet: snippet(&text, start, start + needle.len()),
+                    });
+                });
+            })
+            .take(limit)
+            .collect();
+    }
+}
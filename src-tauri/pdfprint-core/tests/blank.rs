@@ -0,0 +1,49 @@
+use pdfprint_core::blank::{find_blank_pages, BlankOptions};
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf::{extract_document, CombineOptions};
+
+mod common;
+
+use common::{fixture, print_details};
+
+#[test]
+fn finds_blank_pages() -> Result<(), String> {
+    assert_eq!(
+        find_blank_pages(
+            &fixture("sections.pdf"),
+            BlankOptions::default(),
+            &Passwords::default()
+        )?,
+        vec![1]
+    );
+    assert!(find_blank_pages(
+        &fixture("three-pages.pdf"),
+        BlankOptions::default(),
+        &Passwords::default()
+    )?
+    .is_empty());
+
+    return Ok(());
+}
+
+#[test]
+fn skips_blank_pages_when_combining() -> Result<(), String> {
+    let all = extract_document(
+        print_details("sections.pdf", None),
+        CombineOptions::default(),
+        &Passwords::default(),
+    )?;
+    assert_eq!(all.page_count().map_err(|e| return e.to_string())?, 4);
+
+    let skipped = extract_document(
+        print_details("sections.pdf", None),
+        CombineOptions {
+            skip_blank_pages: Some(BlankOptions::default()),
+            ..CombineOptions::default()
+        },
+        &Passwords::default(),
+    )?;
+    assert_eq!(skipped.page_count().map_err(|e| return e.to_string())?, 3);
+
+    return Ok(());
+}
@@ -0,0 +1,176 @@
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf::{
+    collate_order, count_pages, create_combined_pdf, save_document, Collate, CombineOptions,
+    PdfDocument,
+};
+
+mod common;
+
+use common::{fixture, print_details};
+
+#[test]
+fn counts_pages_of_pdfs_and_images() -> Result<(), String> {
+    assert_eq!(
+        count_pages(&fixture("one-page.pdf"), &Passwords::default())?,
+        1
+    );
+    assert_eq!(
+        count_pages(&fixture("three-pages.pdf"), &Passwords::default())?,
+        3
+    );
+    assert_eq!(
+        count_pages(&fixture("landscape.png"), &Passwords::default())?,
+        1
+    );
+
+    return Ok(());
+}
+
+#[test]
+fn pads_odd_documents_for_duplex() -> Result<(), String> {
+    let combined = create_combined_pdf(
+        vec![
+            print_details("one-page.pdf", None),
+            print_details("three-pages.pdf", None),
+        ],
+        CombineOptions::default(),
+        &Passwords::default(),
+    )?;
+
+    // Each document is padded to an even page count: 1 + 1 + 3 + 1
+    assert_eq!(combined.page_count().map_err(|e| return e.to_string())?, 6);
+
+    return Ok(());
+}
+
+#[test]
+fn applies_print_ranges() -> Result<(), String> {
+    let combined = create_combined_pdf(
+        vec![print_details("three-pages.pdf", Some(vec![0, 2]))],
+        CombineOptions::default(),
+        &Passwords::default(),
+    )?;
+
+    assert_eq!(combined.page_count().map_err(|e| return e.to_string())?, 2);
+
+    return Ok(());
+}
+
+#[test]
+fn converts_images_to_pages() -> Result<(), String> {
+    let combined = create_combined_pdf(
+        vec![print_details("landscape.png", None)],
+        CombineOptions::default(),
+        &Passwords::default(),
+    )?;
+
+    assert_eq!(combined.page_count().map_err(|e| return e.to_string())?, 2);
+
+    let page = combined.load_page(0).map_err(|e| return e.to_string())?;
+    let bounds = page.bounds().map_err(|e| return e.to_string())?;
+    // Landscape images get a landscape letter page
+    assert!((bounds.x1 - bounds.x0 - 792.0).abs() < 0.5);
+    assert!((bounds.y1 - bounds.y0 - 612.0).abs() < 0.5);
+
+    return Ok(());
+}
+
+#[test]
+fn lays_out_text_on_pages() -> Result<(), String> {
+    // 120 lines do not fit on one letter page
+    let pages = count_pages(&fixture("notes.txt"), &Passwords::default())?;
+    assert!(pages > 1);
+
+    let combined = create_combined_pdf(
+        vec![print_details("notes.txt", None)],
+        CombineOptions::default(),
+        &Passwords::default(),
+    )?;
+    assert_eq!(
+        combined.page_count().map_err(|e| return e.to_string())?,
+        pages + pages % 2
+    );
+
+    let page = combined.load_page(0).map_err(|e| return e.to_string())?;
+    let bounds = page.bounds().map_err(|e| return e.to_string())?;
+    assert!((bounds.x1 - bounds.x0 - 612.0).abs() < 0.5);
+    assert!((bounds.y1 - bounds.y0 - 792.0).abs() < 0.5);
+
+    return Ok(());
+}
+
+#[test]
+fn saved_documents_can_be_reopened() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    let out = dir.path().join("combined.pdf");
+
+    let combined = create_combined_pdf(
+        vec![print_details("three-pages.pdf", None)],
+        CombineOptions::default(),
+        &Passwords::default(),
+    )?;
+    save_document(&combined, &out)?;
+
+    let reopened = PdfDocument::open(&out.to_string_lossy()).map_err(|e| return e.to_string())?;
+    assert_eq!(reopened.page_count().map_err(|e| return e.to_string())?, 4);
+
+    return Ok(());
+}
+
+#[test]
+fn interleaves_reversed_back_sides() -> Result<(), String> {
+    // Backs scanned by flipping the stack come out last page first
+    assert_eq!(
+        collate_order(3, 3, true)?,
+        vec![(0, 0), (1, 2), (0, 1), (1, 1), (0, 2), (1, 0)]
+    );
+    // The last sheet may have nothing on its back
+    assert_eq!(
+        collate_order(3, 2, false)?,
+        vec![(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)]
+    );
+    assert!(collate_order(3, 1, false).is_err());
+
+    return Ok(());
+}
+
+#[test]
+fn collates_fronts_and_backs_before_ranges() -> Result<(), String> {
+    let collated = |print_range: Option<Vec<i32>>| {
+        let mut details = print_details("three-pages.pdf", print_range);
+        details.collate = Some(Collate {
+            backs: fixture("three-pages.pdf").to_string_lossy().to_string(),
+            reverse_backs: true,
+        });
+        return details;
+    };
+
+    let combined = create_combined_pdf(
+        vec![collated(None)],
+        CombineOptions::default(),
+        &Passwords::default(),
+    )?;
+    assert_eq!(combined.page_count().map_err(|e| return e.to_string())?, 6);
+
+    // The range picks from the collated pages, padded to an even count
+    let combined = create_combined_pdf(
+        vec![collated(Some(vec![0, 1, 5]))],
+        CombineOptions::default(),
+        &Passwords::default(),
+    )?;
+    assert_eq!(combined.page_count().map_err(|e| return e.to_string())?, 4);
+
+    let mut mismatched = print_details("three-pages.pdf", None);
+    mismatched.collate = Some(Collate {
+        backs: fixture("one-page.pdf").to_string_lossy().to_string(),
+        reverse_backs: false,
+    });
+    assert!(create_combined_pdf(
+        vec![mismatched],
+        CombineOptions::default(),
+        &Passwords::default()
+    )
+    .is_err());
+
+    return Ok(());
+}
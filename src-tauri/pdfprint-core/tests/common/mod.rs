@@ -0,0 +1,33 @@
+// Each test binary only uses some of these helpers
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use pdfprint_core::pdf::PdfPrintDetails;
+
+pub fn fixture(name: &str) -> PathBuf {
+    return PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name);
+}
+
+/// Copies a fixture to `to`, the full path of the copy
+pub fn copy_fixture(name: &str, to: &Path) -> Result<(), String> {
+    std::fs::copy(fixture(name), to).map_err(|e| return e.to_string())?;
+
+    return Ok(());
+}
+
+pub fn print_details(name: &str, print_range: Option<Vec<i32>>) -> PdfPrintDetails {
+    return PdfPrintDetails {
+        name: name.to_string(),
+        pages: 0,
+        size: 0,
+        path: fixture(name).to_string_lossy().to_string(),
+        print_range,
+        collate: None,
+        select: None,
+        redactions: vec![],
+    };
+}
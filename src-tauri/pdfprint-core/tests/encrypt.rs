@@ -0,0 +1,129 @@
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf::{
+    create_combined_pdf, save_encrypted_document, CombineOptions, EncryptionMethod,
+    EncryptionOptions, PdfDocument, Permissions,
+};
+
+mod common;
+
+use common::print_details;
+
+fn encrypted_copy(encryption: &EncryptionOptions) -> Result<tempfile::TempDir, String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    let combined = create_combined_pdf(
+        vec![print_details("three-pages.pdf", None)],
+        CombineOptions::default(),
+        &Passwords::default(),
+    )?;
+    save_encrypted_document(&combined, &dir.path().join("out.pdf"), encryption)?;
+
+    return Ok(dir);
+}
+
+fn open(dir: &tempfile::TempDir) -> Result<PdfDocument, String> {
+    return PdfDocument::open(&dir.path().join("out.pdf").to_string_lossy())
+        .map_err(|e| return e.to_string());
+}
+
+/// Permission bits of the `P` entry in the encryption dictionary, as numbered in the PDF spec
+const PRINT: i32 = 1 << 2;
+const MODIFY: i32 = 1 << 3;
+const COPY: i32 = 1 << 4;
+const ANNOTATE: i32 = 1 << 5;
+const PRINT_HQ: i32 = 1 << 11;
+
+/// The permission flags written to the file
+fn permission_flags(dir: &tempfile::TempDir) -> Result<i32, String> {
+    let document = open(dir)?;
+    let encrypt = document
+        .trailer()
+        .and_then(|trailer| return trailer.get_dict("Encrypt"))
+        .map_err(|e| return e.to_string())?
+        .ok_or_else(|| return "The file is not encrypted".to_string())?;
+    let flags = encrypt
+        .get_dict("P")
+        .map_err(|e| return e.to_string())?
+        .ok_or_else(|| return "The file has no permission flags".to_string())?;
+    return flags.as_int().map_err(|e| return e.to_string());
+}
+
+#[test]
+fn opens_only_with_the_password() -> Result<(), String> {
+    for method in [EncryptionMethod::Aes128, EncryptionMethod::Aes256] {
+        let dir = encrypted_copy(&EncryptionOptions {
+            user_password: "bella".to_string(),
+            owner_password: "vet-office".to_string(),
+            method,
+            ..EncryptionOptions::default()
+        })?;
+
+        let mut document = open(&dir)?;
+        assert!(document
+            .needs_password()
+            .map_err(|e| return e.to_string())?);
+        assert!(!document
+            .authenticate("max")
+            .map_err(|e| return e.to_string())?);
+        assert!(document
+            .authenticate("bella")
+            .map_err(|e| return e.to_string())?);
+        assert_eq!(document.page_count().map_err(|e| return e.to_string())?, 4);
+
+        // The owner password opens it as well
+        let mut document = open(&dir)?;
+        assert!(document
+            .authenticate("vet-office")
+            .map_err(|e| return e.to_string())?);
+    }
+
+    return Ok(());
+}
+
+#[test]
+fn owner_password_alone_only_restricts() -> Result<(), String> {
+    let dir = encrypted_copy(&EncryptionOptions {
+        owner_password: "vet-office".to_string(),
+        ..EncryptionOptions::default()
+    })?;
+
+    let document = open(&dir)?;
+    assert!(!document
+        .needs_password()
+        .map_err(|e| return e.to_string())?);
+    assert_eq!(document.page_count().map_err(|e| return e.to_string())?, 4);
+
+    return Ok(());
+}
+
+#[test]
+fn refuses_to_encrypt_without_a_password() {
+    assert!(encrypted_copy(&EncryptionOptions::default()).is_err());
+}
+
+#[test]
+fn writes_the_permissions() -> Result<(), String> {
+    let dir = encrypted_copy(&EncryptionOptions {
+        owner_password: "vet-office".to_string(),
+        ..EncryptionOptions::default()
+    })?;
+    // Printing only by default
+    let flags = permission_flags(&dir)?;
+    assert_eq!(flags & (PRINT | PRINT_HQ), PRINT | PRINT_HQ);
+    assert_eq!(flags & (COPY | MODIFY | ANNOTATE), 0);
+
+    let dir = encrypted_copy(&EncryptionOptions {
+        owner_password: "vet-office".to_string(),
+        permissions: Permissions {
+            print: false,
+            copy: true,
+            modify: false,
+            annotate: true,
+        },
+        ..EncryptionOptions::default()
+    })?;
+    let flags = permission_flags(&dir)?;
+    assert_eq!(flags & (PRINT | PRINT_HQ | MODIFY), 0);
+    assert_eq!(flags & (COPY | ANNOTATE), COPY | ANNOTATE);
+
+    return Ok(());
+}
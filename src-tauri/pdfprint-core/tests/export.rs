@@ -0,0 +1,207 @@
+use std::fs::File;
+use std::io::Read;
+
+use pdfprint_core::export::{export_documents, ExportFormat, ExportOptions, MANIFEST_FILE};
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf::PdfDocument;
+
+mod common;
+
+use common::print_details;
+
+fn page_count(bytes: &[u8]) -> Result<i32, String> {
+    let document = PdfDocument::from_bytes(bytes).map_err(|e| return e.to_string())?;
+    return document.page_count().map_err(|e| return e.to_string());
+}
+
+#[test]
+fn exports_separate_files_with_a_manifest() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    let out = dir.path().join("pack");
+
+    let manifest = export_documents(
+        vec![
+            print_details("three-pages.pdf", Some(vec![0, 2])),
+            print_details("landscape.png", None),
+        ],
+        &out,
+        &ExportOptions::default(),
+        &Passwords::default(),
+    )?;
+
+    let names: Vec<&str> = manifest
+        .files
+        .iter()
+        .map(|entry| return entry.file.as_str())
+        .collect();
+    assert_eq!(names, vec!["1-three-pages.pdf", "2-landscape.pdf"]);
+    assert_eq!(manifest.files[0].range.as_deref(), Some("1,3"));
+    assert_eq!(manifest.files[1].range, None);
+
+    // No padding pages, unlike a combined print
+    let first = std::fs::read(out.join("1-three-pages.pdf")).map_err(|e| return e.to_string())?;
+    assert_eq!(page_count(&first)?, 2);
+    let second = std::fs::read(out.join("2-landscape.pdf")).map_err(|e| return e.to_string())?;
+    assert_eq!(page_count(&second)?, 1);
+
+    let written =
+        std::fs::read_to_string(out.join(MANIFEST_FILE)).map_err(|e| return e.to_string())?;
+    let written: serde_json::Value =
+        serde_json::from_str(&written).map_err(|e| return e.to_string())?;
+    assert_eq!(written["files"][0]["pages"], 2);
+
+    return Ok(());
+}
+
+#[test]
+fn exports_into_a_zip_archive() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    let out = dir.path().join("pack.zip");
+
+    export_documents(
+        vec![
+            print_details("one-page.pdf", None),
+            print_details("one-page.pdf", None),
+        ],
+        &out,
+        &ExportOptions {
+            format: ExportFormat::Zip,
+            name_template: "{name} ({pages} pages)".to_string(),
+            ..ExportOptions::default()
+        },
+        &Passwords::default(),
+    )?;
+
+    let file = File::open(&out).map_err(|e| return e.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| return e.to_string())?;
+    let mut names: Vec<&str> = archive.file_names().collect();
+    names.sort_unstable();
+    // Names that would clash get a counter
+    assert_eq!(
+        names,
+        vec![
+            MANIFEST_FILE,
+            "one-page (1 pages) (2).pdf",
+            "one-page (1 pages).pdf"
+        ]
+    );
+
+    let mut bytes = vec![];
+    archive
+        .by_name("one-page (1 pages).pdf")
+        .map_err(|e| return e.to_string())?
+        .read_to_end(&mut bytes)
+        .map_err(|e| return e.to_string())?;
+    assert_eq!(page_count(&bytes)?, 1);
+
+    return Ok(());
+}
+
+#[test]
+fn templates_can_not_escape_the_output_folder() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    let out = dir.path().join("pack");
+
+    let manifest = export_documents(
+        vec![print_details("one-page.pdf", None)],
+        &out,
+        &ExportOptions {
+            name_template: "../{name}".to_string(),
+            ..ExportOptions::default()
+        },
+        &Passwords::default(),
+    )?;
+
+    assert_eq!(manifest.files[0].file, "_one-page.pdf");
+    assert!(out.join("_one-page.pdf").is_file());
+
+    return Ok(());
+}
+
+#[test]
+fn keeps_files_already_in_the_folder() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    std::fs::write(dir.path().join("1-one-page.pdf"), "mine").map_err(|e| return e.to_string())?;
+    std::fs::write(dir.path().join(MANIFEST_FILE), "{}").map_err(|e| return e.to_string())?;
+
+    let manifest = export_documents(
+        vec![print_details("one-page.pdf", None)],
+        dir.path(),
+        &ExportOptions::default(),
+        &Passwords::default(),
+    )?;
+
+    assert_eq!(manifest.files[0].file, "1-one-page (2).pdf");
+    assert_eq!(manifest.manifest_file, "manifest (2).json");
+    let kept = std::fs::read_to_string(dir.path().join("1-one-page.pdf"))
+        .map_err(|e| return e.to_string())?;
+    assert_eq!(kept, "mine");
+    let kept = std::fs::read_to_string(dir.path().join(MANIFEST_FILE))
+        .map_err(|e| return e.to_string())?;
+    assert_eq!(kept, "{}");
+    assert!(dir.path().join("manifest (2).json").is_file());
+
+    return Ok(());
+}
+
+#[test]
+fn records_ranges_in_the_order_exported() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+
+    let manifest = export_documents(
+        vec![print_details("three-pages.pdf", Some(vec![2, 0, 1]))],
+        dir.path(),
+        &ExportOptions::default(),
+        &Passwords::default(),
+    )?;
+
+    assert_eq!(manifest.files[0].range.as_deref(), Some("3,1-2"));
+    assert_eq!(manifest.files[0].pages, 3);
+
+    return Ok(());
+}
+
+#[test]
+fn copies_attributes_inherited_from_the_page_tree() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+
+    // Both pages inherit Rotate and Resources, only the second has its own MediaBox
+    let manifest = export_documents(
+        vec![print_details("inherited.pdf", None)],
+        dir.path(),
+        &ExportOptions::default(),
+        &Passwords::default(),
+    )?;
+
+    let bytes = std::fs::read(dir.path().join(&manifest.files[0].file))
+        .map_err(|e| return e.to_string())?;
+    let document = PdfDocument::from_bytes(&bytes).map_err(|e| return e.to_string())?;
+    assert_eq!(document.page_count().map_err(|e| return e.to_string())?, 2);
+
+    // Rotated a quarter turn, so the page shows its box on its side
+    for (index, width, height) in [(0, 400.0, 200.0), (1, 300.0, 300.0)] {
+        let page_object = document
+            .find_page(index)
+            .map_err(|e| return e.to_string())?;
+        let rotate = page_object
+            .get_dict("Rotate")
+            .map_err(|e| return e.to_string())?
+            .map(|rotate| return rotate.as_int())
+            .transpose()
+            .map_err(|e| return e.to_string())?;
+        assert_eq!(rotate, Some(90));
+        assert!(page_object
+            .get_dict("Resources")
+            .map_err(|e| return e.to_string())?
+            .is_some());
+
+        let page = document
+            .load_page(index)
+            .map_err(|e| return e.to_string())?;
+        let bounds = page.bounds().map_err(|e| return e.to_string())?;
+        assert!((bounds.x1 - bounds.x0 - width).abs() < 0.5);
+        assert!((bounds.y1 - bounds.y0 - height).abs() < 0.5);
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,97 @@
+use pdfprint_core::entry_id::EntryIds;
+use pdfprint_core::metadata::{parse_pdf_date, write_metadata, MetadataOptions};
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf::{create_combined_pdf, save_document, CombineOptions, PdfDocument};
+use pdfprint_core::scan::{document_details, PdfDetails};
+
+mod common;
+
+use common::print_details;
+
+fn saved_with(options: &MetadataOptions) -> Result<(tempfile::TempDir, PdfDetails), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    let out = dir.path().join("records.pdf");
+
+    let mut combined = create_combined_pdf(
+        vec![print_details("three-pages.pdf", None)],
+        CombineOptions::default(),
+        &Passwords::default(),
+    )?;
+    write_metadata(&mut combined, options, "records")?;
+    save_document(&combined, &out)?;
+
+    let metadata = std::fs::metadata(&out).map_err(|e| return e.to_string())?;
+    let details = document_details(
+        &out,
+        &metadata,
+        None,
+        &mut EntryIds::default(),
+        &Passwords::default(),
+    )?;
+
+    return Ok((dir, details));
+}
+
+#[test]
+fn parses_pdf_dates() {
+    assert_eq!(
+        parse_pdf_date("D:20240301093000+01'00'").as_deref(),
+        Some("2024-03-01T09:30:00+01:00")
+    );
+    assert_eq!(
+        parse_pdf_date("D:20240301093000Z").as_deref(),
+        Some("2024-03-01T09:30:00Z")
+    );
+    assert_eq!(
+        parse_pdf_date("D:2024").as_deref(),
+        Some("2024-01-01T00:00:00Z")
+    );
+    assert_eq!(parse_pdf_date("yesterday"), None);
+}
+
+#[test]
+fn writes_metadata_that_scans_read_back() -> Result<(), String> {
+    let (dir, details) = saved_with(&MetadataOptions {
+        title: Some("Bella & Max".to_string()),
+        author: Some("Riverside Vets".to_string()),
+        keywords: Some("vaccinations".to_string()),
+        ..MetadataOptions::default()
+    })?;
+
+    assert_eq!(details.metadata.title.as_deref(), Some("Bella & Max"));
+    assert_eq!(details.metadata.author.as_deref(), Some("Riverside Vets"));
+    assert_eq!(details.metadata.keywords.as_deref(), Some("vaccinations"));
+    assert_eq!(details.metadata.subject, None);
+    assert!(details.metadata.created.is_some());
+    assert!(details.metadata.version.is_some());
+
+    let document = PdfDocument::open(&dir.path().join("records.pdf").to_string_lossy())
+        .map_err(|e| return e.to_string())?;
+    let xmp = document
+        .catalog()
+        .and_then(|catalog| return catalog.get_dict("Metadata"))
+        .map_err(|e| return e.to_string())?;
+    assert!(xmp.is_some());
+
+    return Ok(());
+}
+
+#[test]
+fn titles_default_to_the_job_name_and_date() -> Result<(), String> {
+    let (_dir, details) = saved_with(&MetadataOptions {
+        job_name: Some("Vaccinations".to_string()),
+        ..MetadataOptions::default()
+    })?;
+    let title = details.metadata.title.unwrap_or_default();
+    assert!(title.starts_with("Vaccinations "));
+    // Followed by a date such as 2024-03-01
+    assert_eq!(title.len(), "Vaccinations ".len() + 10);
+
+    let (_dir, details) = saved_with(&MetadataOptions::default())?;
+    assert!(details
+        .metadata
+        .title
+        .is_some_and(|title| return title.starts_with("records ")));
+
+    return Ok(());
+}
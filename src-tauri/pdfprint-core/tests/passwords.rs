@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use pdfprint_core::entry_id::EntryIds;
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf::{count_pages, create_combined_pdf, CombineOptions};
+use pdfprint_core::scan::document_details;
+
+mod common;
+
+use common::{fixture, print_details};
+
+fn list(path: &Path, passwords: &Passwords) -> Result<pdfprint_core::scan::PdfDetails, String> {
+    let metadata = std::fs::metadata(path).map_err(|e| return e.to_string())?;
+    return document_details(path, &metadata, None, &mut EntryIds::default(), passwords);
+}
+
+#[test]
+fn lists_locked_documents() -> Result<(), String> {
+    let passwords = Passwords::default();
+
+    let listed = list(&fixture("protected.pdf"), &passwords)?;
+    assert!(listed.encrypted);
+    assert!(listed.locked);
+    assert_eq!(listed.pages, 0);
+
+    let listed = list(&fixture("one-page.pdf"), &passwords)?;
+    assert!(!listed.encrypted);
+
+    return Ok(());
+}
+
+#[test]
+fn combines_documents_once_unlocked() -> Result<(), String> {
+    let passwords = Passwords::default();
+    let path = fixture("protected.pdf");
+    let protected = || return vec![print_details("protected.pdf", None)];
+
+    assert!(create_combined_pdf(protected(), CombineOptions::default(), &passwords).is_err());
+    assert!(passwords.unlock(&path, "rex").is_err());
+    assert!(passwords.is_locked(&path));
+
+    passwords.unlock(&path, "bella")?;
+    assert!(!passwords.is_locked(&path));
+    assert_eq!(count_pages(&path, &passwords)?, 2);
+
+    let combined = create_combined_pdf(protected(), CombineOptions::default(), &passwords)?;
+    assert_eq!(combined.page_count().map_err(|e| return e.to_string())?, 2);
+
+    let listed = list(&path, &passwords)?;
+    assert!(listed.encrypted);
+    assert!(!listed.locked);
+    assert_eq!(listed.pages, 2);
+
+    return Ok(());
+}
+
+#[test]
+fn keeps_unlocked_passwords_to_their_own_store() -> Result<(), String> {
+    let path = fixture("protected.pdf");
+    let unlocked = Passwords::default();
+    unlocked.unlock(&path, "bella")?;
+
+    let other = Passwords::default();
+    assert!(other.is_locked(&path));
+    assert!(count_pages(&path, &other).is_err());
+    assert!(!unlocked.is_locked(&path));
+
+    return Ok(());
+}
@@ -0,0 +1,172 @@
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf::{CombineOptions, ImagePageSize, PdfPrintDetails};
+use pdfprint_core::preflight::{preflight, PreflightWarning};
+use pdfprint_core::printing::{PrintOptions, Sides};
+
+mod common;
+
+use common::{fixture, print_details};
+
+#[test]
+fn counts_pages_and_sheets() {
+    let pdfs = || {
+        return vec![
+            print_details("one-page.pdf", None),
+            print_details("three-pages.pdf", None),
+            print_details("landscape.png", None),
+        ];
+    };
+
+    let report = preflight(
+        pdfs(),
+        CombineOptions::default(),
+        &PrintOptions::default(),
+        &Passwords::default(),
+    );
+    // 1 + 1 blank, 3 + 1 blank, 1 + 1 blank
+    assert_eq!(report.pages, 8);
+    assert_eq!(report.sheets, 4);
+    assert!(!report
+        .warnings
+        .iter()
+        .any(|warning| return matches!(warning, PreflightWarning::MixedPageSizes { .. })));
+
+    let report = preflight(
+        pdfs(),
+        CombineOptions::default(),
+        &PrintOptions {
+            copies: Some(2),
+            sides: Some(Sides::OneSided),
+            ..PrintOptions::default()
+        },
+        &Passwords::default(),
+    );
+    assert_eq!(report.sheets, 16);
+}
+
+#[test]
+fn warns_about_mixed_sizes_and_missing_fonts() {
+    let report = preflight(
+        vec![
+            print_details("three-pages.pdf", None),
+            print_details("landscape.png", None),
+        ],
+        CombineOptions {
+            image_page_size: ImagePageSize::Original,
+            ..CombineOptions::default()
+        },
+        &PrintOptions::default(),
+        &Passwords::default(),
+    );
+
+    assert!(matches!(
+        report.warnings.first(),
+        Some(PreflightWarning::MixedPageSizes { sizes }) if sizes.len() == 2
+    ));
+    assert!(report
+        .warnings
+        .contains(&PreflightWarning::UnembeddedFonts {
+            document: "three-pages.pdf".to_string(),
+            fonts: vec!["Helvetica".to_string()],
+        }));
+}
+
+#[test]
+fn reports_locked_documents_instead_of_failing() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    let path = dir.path().join("protected.pdf");
+    std::fs::copy(fixture("protected.pdf"), &path).map_err(|e| return e.to_string())?;
+
+    let report = preflight(
+        vec![
+            PdfPrintDetails {
+                path: path.to_string_lossy().to_string(),
+                ..print_details("protected.pdf", None)
+            },
+            print_details("one-page.pdf", None),
+        ],
+        CombineOptions::default(),
+        &PrintOptions::default(),
+        &Passwords::default(),
+    );
+
+    assert!(report.warnings.contains(&PreflightWarning::Encrypted {
+        document: "protected.pdf".to_string(),
+        locked: true,
+    }));
+    assert!(report.warnings.iter().any(|warning| {
+        return matches!(warning, PreflightWarning::Unreadable { document, .. } if document == "protected.pdf");
+    }));
+    assert_eq!(report.pages, 2);
+
+    return Ok(());
+}
+
+#[test]
+fn warns_about_rotated_pages_inherited_from_the_page_tree() {
+    let report = preflight(
+        vec![print_details("inherited.pdf", None)],
+        CombineOptions::default(),
+        &PrintOptions::default(),
+        &Passwords::default(),
+    );
+
+    assert!(report.warnings.contains(&PreflightWarning::RotatedPages {
+        document: "inherited.pdf".to_string(),
+        pages: vec![1, 2],
+    }));
+}
+
+#[test]
+fn warns_about_transparency_and_high_resolution_images() {
+    let report = preflight(
+        vec![print_details("hazards.pdf", None)],
+        CombineOptions::default(),
+        &PrintOptions::default(),
+        &Passwords::default(),
+    );
+
+    assert!(report.warnings.contains(&PreflightWarning::Transparency {
+        document: "hazards.pdf".to_string(),
+        pages: vec![1],
+    }));
+    // 1400 pixels across a page 2 inches wide
+    assert!(report
+        .warnings
+        .contains(&PreflightWarning::HighResolutionImages {
+            document: "hazards.pdf".to_string(),
+            pages: vec![2],
+            dpi: 700,
+        }));
+}
+
+#[test]
+fn counts_padding_pages_as_a_page_size() {
+    let report = preflight(
+        vec![print_details("hazards.pdf", Some(vec![0]))],
+        CombineOptions::default(),
+        &PrintOptions::default(),
+        &Passwords::default(),
+    );
+
+    assert_eq!(
+        report.warnings.first(),
+        Some(&PreflightWarning::MixedPageSizes {
+            sizes: vec!["144x144".to_string(), "612x792".to_string()],
+        })
+    );
+}
+
+#[test]
+fn warns_about_repaired_documents() {
+    let report = preflight(
+        vec![print_details("damaged.pdf", None)],
+        CombineOptions::default(),
+        &PrintOptions::default(),
+        &Passwords::default(),
+    );
+
+    assert!(report.warnings.contains(&PreflightWarning::Repaired {
+        document: "damaged.pdf".to_string(),
+    }));
+}
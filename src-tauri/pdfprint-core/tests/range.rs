@@ -0,0 +1,57 @@
+use pdfprint_core::range::{format_page_range, looks_like_range, parse_page_range};
+
+#[test]
+fn parses_single_pages_and_ranges() -> Result<(), String> {
+    assert_eq!(parse_page_range("1-3,5", 6)?, vec![0, 1, 2, 4]);
+
+    return Ok(());
+}
+
+#[test]
+fn keeps_the_order_given() -> Result<(), String> {
+    assert_eq!(parse_page_range("3,1", 4)?, vec![2, 0]);
+    assert_eq!(parse_page_range("4,1-2,2", 4)?, vec![3, 0, 1, 1]);
+
+    return Ok(());
+}
+
+#[test]
+fn open_ranges_run_to_the_document_edges() -> Result<(), String> {
+    assert_eq!(parse_page_range("-2", 4)?, vec![0, 1]);
+    assert_eq!(parse_page_range("3-", 4)?, vec![2, 3]);
+
+    return Ok(());
+}
+
+#[test]
+fn rejects_out_of_range_and_malformed_input() {
+    assert!(parse_page_range("0", 4).is_err());
+    assert!(parse_page_range("5", 4).is_err());
+    assert!(parse_page_range("3-1", 4).is_err());
+    assert!(parse_page_range("1,,2", 4).is_err());
+    assert!(parse_page_range("a", 4).is_err());
+}
+
+#[test]
+fn recognizes_range_suffixes() {
+    assert!(looks_like_range("1-3,5"));
+    assert!(!looks_like_range(r"\file.pdf"));
+    assert!(!looks_like_range(""));
+}
+
+#[test]
+fn formats_pages_back_into_ranges() -> Result<(), String> {
+    assert_eq!(format_page_range(&[0, 1, 2, 4]), "1-3,5");
+    assert_eq!(format_page_range(&[4, 0, 1, 2, 2]), "5,1-3,3");
+    assert_eq!(format_page_range(&[]), "");
+    assert_eq!(
+        parse_page_range(&format_page_range(&[0, 2, 3]), 4)?,
+        vec![0, 2, 3]
+    );
+    assert_eq!(
+        parse_page_range(&format_page_range(&[3, 0, 1, 1]), 4)?,
+        vec![3, 0, 1, 1]
+    );
+
+    return Ok(());
+}
@@ -0,0 +1,137 @@
+use mupdf::Document;
+use pdfprint_core::blank::ink_coverage;
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf::{
+    document_bytes, extract_document, CombineOptions, PdfDocument, PdfPrintDetails,
+};
+use pdfprint_core::redact::{Redaction, Redactor};
+use pdfprint_core::render::render_page_png;
+use pdfprint_core::text_index::page_text;
+
+mod common;
+
+use common::print_details;
+
+fn redacted(redactions: Vec<Redaction>) -> Result<PdfDocument, String> {
+    let mut sections = print_details("sections.pdf", None);
+    sections.redactions = redactions;
+    return extract_document(sections, CombineOptions::default(), &Passwords::default());
+}
+
+fn text_of(document: &PdfDocument, page: i32) -> Result<String, String> {
+    return page_text(&document.load_page(page).map_err(|e| return e.to_string())?);
+}
+
+#[test]
+fn removes_matching_text() -> Result<(), String> {
+    let document = redacted(vec![Redaction::Text {
+        pattern: "max".to_string(),
+        regex: false,
+        case_sensitive: false,
+    }])?;
+
+    assert!(!text_of(&document, 2)?.contains("Max"));
+    assert!(!text_of(&document, 3)?.contains("Max"));
+    // Only the match goes, not the rest of the line
+    assert!(text_of(&document, 3)?.contains("vaccinations"));
+    assert_eq!(text_of(&document, 0)?, "Bella");
+
+    return Ok(());
+}
+
+#[test]
+fn removes_content_under_areas() -> Result<(), String> {
+    let untouched = redacted(vec![])?;
+    let before = ink_coverage(&untouched.load_page(0).map_err(|e| return e.to_string())?)?;
+
+    // The square drawn on the first page, in points from the top left corner
+    let document = redacted(vec![Redaction::Area {
+        page: 0,
+        x0: 60.0,
+        y0: 280.0,
+        x1: 290.0,
+        y1: 510.0,
+    }])?;
+    let after = ink_coverage(&document.load_page(0).map_err(|e| return e.to_string())?)?;
+
+    assert!(before > 0.05);
+    assert!(after < 0.01);
+    assert_eq!(text_of(&document, 0)?, "Bella");
+    // Other pages keep their square
+    let other = ink_coverage(&document.load_page(2).map_err(|e| return e.to_string())?)?;
+    assert!(other > 0.05);
+
+    return Ok(());
+}
+
+#[test]
+fn leaves_no_redacted_content_in_saved_files() -> Result<(), String> {
+    let contains = |bytes: &[u8], text: &[u8]| {
+        return bytes
+            .windows(text.len())
+            .any(|window| return window == text);
+    };
+    let untouched = document_bytes(&redacted(vec![])?)?;
+    assert!(contains(&untouched, b"Max"));
+
+    let saved = document_bytes(&redacted(vec![Redaction::Text {
+        pattern: "max".to_string(),
+        regex: false,
+        case_sensitive: false,
+    }])?)?;
+    // The covered text is gone from the file, not only from what is drawn
+    assert!(!contains(&saved, b"Max"));
+
+    return Ok(());
+}
+
+#[test]
+fn counts_text_matches() -> Result<(), String> {
+    let document = redacted(vec![])?;
+    let text = |pattern: &str| {
+        return Redactor::new(&[Redaction::Text {
+            pattern: pattern.to_string(),
+            regex: false,
+            case_sensitive: false,
+        }]);
+    };
+
+    assert_eq!(text("max")?.apply(&document, 3, 3)?, 1);
+    assert_eq!(text("rex")?.apply(&document, 3, 3)?, 0);
+
+    return Ok(());
+}
+
+/// Ink coverage of a page rendered for the preview
+fn preview_coverage(sections: PdfPrintDetails, position: i32) -> Result<f32, String> {
+    let png = render_page_png(
+        sections,
+        position,
+        36.0,
+        CombineOptions::default(),
+        &Passwords::default(),
+    )?;
+    assert!(png.starts_with(b"\x89PNG"));
+
+    let image = Document::from_bytes(&png, "png").map_err(|e| return e.to_string())?;
+    return ink_coverage(&image.load_page(0).map_err(|e| return e.to_string())?);
+}
+
+#[test]
+fn renders_redacted_previews() -> Result<(), String> {
+    let mut sections = print_details("sections.pdf", None);
+    // Printed out of order, so preview positions can not be mistaken for printed pages
+    sections.print_range = Some(vec![2, 0]);
+    sections.redactions = vec![Redaction::Area {
+        page: 0,
+        x0: 60.0,
+        y0: 280.0,
+        x1: 290.0,
+        y1: 510.0,
+    }];
+
+    assert!(preview_coverage(sections.clone(), 0)? < 0.01);
+    assert!(preview_coverage(sections, 2)? > 0.05);
+
+    return Ok(());
+}
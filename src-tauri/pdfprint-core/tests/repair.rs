@@ -0,0 +1,91 @@
+use std::path::Path;
+
+use pdfprint_core::entry_id::EntryIds;
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::repair::{repair_pdf, REBUILT_WARNING};
+use pdfprint_core::scan::{document_details, PdfDetails};
+
+mod common;
+
+use common::fixture;
+
+fn details(path: &Path) -> Result<PdfDetails, String> {
+    let metadata = std::fs::metadata(path).map_err(|e| return e.to_string())?;
+    return document_details(
+        path,
+        &metadata,
+        None,
+        &mut EntryIds::default(),
+        &Passwords::default(),
+    );
+}
+
+#[test]
+fn lists_damaged_documents_with_warnings() -> Result<(), String> {
+    let listed = details(&fixture("damaged.pdf"))?;
+    assert!(listed.damaged);
+    assert_eq!(listed.warnings, vec![REBUILT_WARNING.to_string()]);
+    assert_eq!(listed.pages, 3);
+
+    let listed = details(&fixture("three-pages.pdf"))?;
+    assert!(!listed.damaged);
+    assert!(listed.warnings.is_empty());
+
+    return Ok(());
+}
+
+#[test]
+fn lists_documents_that_can_not_be_opened() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    let path = dir.path().join("not-a.pdf");
+    std::fs::write(&path, "not a pdf").map_err(|e| return e.to_string())?;
+
+    let listed = details(&path)?;
+    assert!(listed.damaged);
+    assert_eq!(listed.pages, 0);
+    assert_eq!(listed.warnings.len(), 1);
+    assert!(repair_pdf(&path, &dir.path().join("out.pdf"), &Passwords::default()).is_err());
+
+    return Ok(());
+}
+
+#[test]
+fn writes_a_clean_copy() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    let out = dir.path().join("repaired.pdf");
+
+    let report = repair_pdf(&fixture("damaged.pdf"), &out, &Passwords::default())?;
+    assert_eq!(report.pages, 3);
+    assert_eq!(report.warnings, vec![REBUILT_WARNING.to_string()]);
+
+    assert_eq!(report.backup, None);
+
+    let listed = details(&out)?;
+    assert!(!listed.damaged);
+    assert_eq!(listed.pages, 3);
+
+    return Ok(());
+}
+
+#[test]
+fn repairs_in_place() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    let path = dir.path().join("damaged.pdf");
+    std::fs::copy(fixture("damaged.pdf"), &path).map_err(|e| return e.to_string())?;
+
+    let report = repair_pdf(&path, &path, &Passwords::default())?;
+    let listed = details(&path)?;
+    assert!(!listed.damaged);
+    assert_eq!(listed.pages, 3);
+    assert!(!dir.path().join("damaged.repairing.pdf").exists());
+
+    // The damaged original is kept alongside
+    let backup = report.backup.ok_or("No backup of the original")?;
+    assert!(backup.contains("damaged.pdf.damaged-"));
+    assert_eq!(
+        std::fs::read(&backup).map_err(|e| return e.to_string())?,
+        std::fs::read(fixture("damaged.pdf")).map_err(|e| return e.to_string())?
+    );
+
+    return Ok(());
+}
@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use pdfprint_core::entry_id::EntryIds;
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::scan::{document_details, scan_folder, DocumentFormat, Entry};
+use pdfprint_core::scan_filter::{ScanFilter, ScanFilters};
+
+mod common;
+
+use common::copy_fixture;
+
+fn names(entries: &[Entry]) -> Vec<String> {
+    let mut names: Vec<String> = entries
+        .iter()
+        .map(|entry| {
+            return match entry {
+                Entry::PdfDetails(details) => details.name.clone(),
+                Entry::DirEntry(dir) => format!("{}/", dir.name),
+            };
+        })
+        .collect();
+    names.sort();
+
+    return names;
+}
+
+fn workspace() -> Result<tempfile::TempDir, String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    let root = dir.path();
+
+    copy_fixture("one-page.pdf", &root.join("one-page.pdf"))?;
+    copy_fixture("three-pages.pdf", &root.join("SCAN.PDF"))?;
+    copy_fixture("one-page.pdf", &root.join(".hidden.pdf"))?;
+    copy_fixture("landscape.png", &root.join("photo.png"))?;
+    fs::write(root.join("notes.txt"), "not a document").map_err(|e| return e.to_string())?;
+    fs::create_dir(root.join("archive")).map_err(|e| return e.to_string())?;
+    fs::create_dir(root.join("Report.pages")).map_err(|e| return e.to_string())?;
+
+    return Ok(dir);
+}
+
+#[test]
+fn lists_documents_with_default_filters() -> Result<(), String> {
+    let dir = workspace()?;
+    let filter = ScanFilter::new(dir.path(), ScanFilters::default());
+
+    let entries = scan_folder(
+        dir.path(),
+        &filter,
+        &mut EntryIds::default(),
+        &Passwords::default(),
+    )?;
+
+    assert_eq!(
+        names(&entries),
+        vec!["SCAN.PDF", "archive/", "one-page.pdf", "photo.png"]
+    );
+
+    let scan = entries.iter().find_map(|entry| {
+        return match entry {
+            Entry::PdfDetails(details) if details.name == "SCAN.PDF" => Some(details),
+            _ => None,
+        };
+    });
+    assert_eq!(scan.map(|details| return details.pages), Some(3));
+
+    let photo = entries.iter().find_map(|entry| {
+        return match entry {
+            Entry::PdfDetails(details) if details.name == "photo.png" => Some(details),
+            _ => None,
+        };
+    });
+    assert_eq!(
+        photo.map(|details| return details.format),
+        Some(DocumentFormat::Image)
+    );
+
+    return Ok(());
+}
+
+#[test]
+fn applies_configured_filters() -> Result<(), String> {
+    let dir = workspace()?;
+    let filter = ScanFilter::new(
+        dir.path(),
+        ScanFilters {
+            extensions: vec![".PDF".to_string()],
+            include_hidden: true,
+            ignore: vec!["/archive".to_string(), "SCAN.*".to_string()],
+            ..ScanFilters::default()
+        },
+    );
+
+    let entries = scan_folder(
+        dir.path(),
+        &filter,
+        &mut EntryIds::default(),
+        &Passwords::default(),
+    )?;
+
+    assert_eq!(names(&entries), vec![".hidden.pdf", "one-page.pdf"]);
+
+    return Ok(());
+}
+
+#[test]
+fn skips_files_over_the_size_limit() -> Result<(), String> {
+    let dir = workspace()?;
+    let small = fs::metadata(dir.path().join("one-page.pdf"))
+        .map_err(|e| return e.to_string())?
+        .len();
+    let filter = ScanFilter::new(
+        dir.path(),
+        ScanFilters {
+            max_file_size: Some(small),
+            ..ScanFilters::default()
+        },
+    );
+
+    let entries = scan_folder(
+        dir.path(),
+        &filter,
+        &mut EntryIds::default(),
+        &Passwords::default(),
+    )?;
+
+    assert_eq!(
+        names(&entries),
+        vec!["archive/", "one-page.pdf", "photo.png"]
+    );
+
+    return Ok(());
+}
+
+#[test]
+fn keeps_entry_ids_across_renames() -> Result<(), String> {
+    let dir = workspace()?;
+    let mut ids = EntryIds::default();
+    let before = ids.id_for(&dir.path().join("one-page.pdf"));
+
+    fs::rename(
+        dir.path().join("one-page.pdf"),
+        dir.path().join("renamed.pdf"),
+    )
+    .map_err(|e| return e.to_string())?;
+
+    // A fresh registry, as after a restart
+    let after = EntryIds::default().id_for(&dir.path().join("renamed.pdf"));
+
+    assert_eq!(before, after);
+
+    return Ok(());
+}
+
+#[test]
+fn keeps_rehashed_entry_ids_whatever_the_order() {
+    let colliding = EntryIds::default().id_for_key("second");
+    // As if "first" had hashed to the same id as "second"
+    let mut ids = EntryIds::with_pinned(BTreeMap::from([("first".to_string(), colliding)]));
+    let rehashed = ids.id_for_key("second");
+    assert_ne!(rehashed, colliding);
+    assert!(ids.take_pinned_changed());
+    assert!(!ids.take_pinned_changed());
+
+    // After a restart "second" is seen before "first"
+    let mut restored = EntryIds::with_pinned(ids.pinned().clone());
+    assert_eq!(restored.id_for_key("second"), rehashed);
+    assert_eq!(restored.id_for_key("first"), colliding);
+    assert!(!restored.take_pinned_changed());
+}
+
+#[test]
+fn gives_loose_files_ids_apart_from_the_workspace() -> Result<(), String> {
+    let dir = workspace()?;
+    let path = dir.path().join("one-page.pdf");
+    let filter = ScanFilter::new(dir.path(), ScanFilters::default());
+    let mut ids = EntryIds::default();
+
+    let listed = scan_folder(dir.path(), &filter, &mut ids, &Passwords::default())?
+        .into_iter()
+        .find_map(|entry| {
+            return match entry {
+                Entry::PdfDetails(details) if details.name == "one-page.pdf" => Some(details.id),
+                _ => None,
+            };
+        });
+
+    // Dropped on the window while it is also listed in the workspace
+    let metadata = fs::metadata(&path).map_err(|e| return e.to_string())?;
+    let mut drop_file = || {
+        return ids.in_namespace("loose", |ids| {
+            return document_details(&path, &metadata, None, ids, &Passwords::default());
+        });
+    };
+    let loose = drop_file()?.id;
+    assert!(listed.is_some_and(|listed| return listed != loose));
+    assert_eq!(drop_file()?.id, loose);
+    assert_eq!(Some(ids.id_for(&path)), listed);
+
+    return Ok(());
+}
@@ -0,0 +1,85 @@
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf::{extract_document, preview_selection, CombineOptions};
+use pdfprint_core::select::PageSelector;
+
+mod common;
+
+use common::print_details;
+
+fn selector(pattern: &str) -> PageSelector {
+    return PageSelector {
+        pattern: pattern.to_string(),
+        regex: false,
+        case_sensitive: false,
+        before: 0,
+        after: 0,
+    };
+}
+
+#[test]
+fn previews_pages_matching_text() -> Result<(), String> {
+    let sections = print_details("sections.pdf", None);
+
+    assert_eq!(
+        preview_selection(&sections, &selector("max"), &Passwords::default())?,
+        vec![2, 3]
+    );
+    assert_eq!(
+        preview_selection(&sections, &selector("vaccinations"), &Passwords::default())?,
+        vec![3]
+    );
+    assert!(preview_selection(
+        &sections,
+        &PageSelector {
+            case_sensitive: true,
+            ..selector("max")
+        },
+        &Passwords::default()
+    )?
+    .is_empty());
+
+    return Ok(());
+}
+
+#[test]
+fn includes_adjacent_pages_and_regex_matches() -> Result<(), String> {
+    let sections = print_details("sections.pdf", None);
+
+    let bella_and_back = PageSelector {
+        after: 1,
+        ..selector("bella")
+    };
+    assert_eq!(
+        preview_selection(&sections, &bella_and_back, &Passwords::default())?,
+        vec![0, 1]
+    );
+
+    let whole_title = PageSelector {
+        regex: true,
+        before: 5,
+        ..selector("^max$")
+    };
+    assert_eq!(
+        preview_selection(&sections, &whole_title, &Passwords::default())?,
+        vec![0, 1, 2]
+    );
+
+    let invalid = PageSelector {
+        regex: true,
+        ..selector("(unclosed")
+    };
+    assert!(preview_selection(&sections, &invalid, &Passwords::default()).is_err());
+
+    return Ok(());
+}
+
+#[test]
+fn selectors_narrow_the_print_range() -> Result<(), String> {
+    let mut sections = print_details("sections.pdf", Some(vec![0, 1, 2]));
+    sections.select = Some(selector("max"));
+
+    let document = extract_document(sections, CombineOptions::default(), &Passwords::default())?;
+    assert_eq!(document.page_count().map_err(|e| return e.to_string())?, 1);
+
+    return Ok(());
+}
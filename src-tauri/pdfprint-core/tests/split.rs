@@ -0,0 +1,131 @@
+use pdfprint_core::blank::BlankOptions;
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf::{count_pages, PdfDocument};
+use pdfprint_core::split::{split_document, SplitMode, SplitOptions, DEFAULT_NAME_TEMPLATE};
+
+mod common;
+
+use common::fixture;
+
+fn options(mode: SplitMode, name_template: &str) -> SplitOptions {
+    return SplitOptions {
+        mode,
+        name_template: name_template.to_string(),
+    };
+}
+
+#[test]
+fn splits_by_ranges() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+
+    let parts = split_document(
+        &fixture("three-pages.pdf"),
+        dir.path(),
+        &options(
+            SplitMode::Ranges {
+                ranges: vec!["1-2".to_string(), "3".to_string()],
+            },
+            DEFAULT_NAME_TEMPLATE,
+        ),
+        &Passwords::default(),
+    )?;
+
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].file, "three-pages-1.pdf");
+    assert_eq!(
+        count_pages(&dir.path().join("three-pages-1.pdf"), &Passwords::default())?,
+        2
+    );
+    assert_eq!(
+        count_pages(&dir.path().join("three-pages-2.pdf"), &Passwords::default())?,
+        1
+    );
+
+    return Ok(());
+}
+
+#[test]
+fn splits_every_n_pages() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+
+    let parts = split_document(
+        &fixture("three-pages.pdf"),
+        dir.path(),
+        &options(SplitMode::EveryN { pages: 2 }, "{range}"),
+        &Passwords::default(),
+    )?;
+
+    let names: Vec<&str> = parts.iter().map(|part| return part.file.as_str()).collect();
+    assert_eq!(names, vec!["1-2.pdf", "3.pdf"]);
+
+    return Ok(());
+}
+
+#[test]
+fn splits_at_top_level_bookmarks() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+
+    let parts = split_document(
+        &fixture("sections.pdf"),
+        dir.path(),
+        &options(SplitMode::Bookmarks, "{title}"),
+        &Passwords::default(),
+    )?;
+
+    // The nested bookmark does not start a part of its own
+    let names: Vec<&str> = parts.iter().map(|part| return part.file.as_str()).collect();
+    assert_eq!(names, vec!["Bella.pdf", "Max.pdf"]);
+    assert_eq!(parts[0].range, "1-2");
+    assert_eq!(parts[1].range, "3-4");
+
+    return Ok(());
+}
+
+#[test]
+fn splits_at_blank_pages_and_drops_them() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+
+    let parts = split_document(
+        &fixture("sections.pdf"),
+        dir.path(),
+        &options(
+            SplitMode::BlankSeparators {
+                blank: BlankOptions::default(),
+            },
+            DEFAULT_NAME_TEMPLATE,
+        ),
+        &Passwords::default(),
+    )?;
+
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].range, "1");
+    assert_eq!(parts[1].range, "3-4");
+
+    let second = PdfDocument::open(&dir.path().join(&parts[1].file).to_string_lossy())
+        .map_err(|e| return e.to_string())?;
+    assert_eq!(second.page_count().map_err(|e| return e.to_string())?, 2);
+
+    return Ok(());
+}
+
+#[test]
+fn never_replaces_existing_files() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    std::fs::write(dir.path().join("three-pages-1.pdf"), b"keep")
+        .map_err(|e| return e.to_string())?;
+
+    let parts = split_document(
+        &fixture("three-pages.pdf"),
+        dir.path(),
+        &options(SplitMode::EveryN { pages: 3 }, DEFAULT_NAME_TEMPLATE),
+        &Passwords::default(),
+    )?;
+
+    assert_eq!(parts[0].file, "three-pages-1 (2).pdf");
+    assert_eq!(
+        std::fs::read(dir.path().join("three-pages-1.pdf")).map_err(|e| return e.to_string())?,
+        b"keep"
+    );
+
+    return Ok(());
+}
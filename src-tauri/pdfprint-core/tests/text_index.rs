@@ -0,0 +1,96 @@
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::scan_filter::{ScanFilter, ScanFilters};
+use pdfprint_core::text_index::{IndexUpdate, TextIndex};
+
+mod common;
+
+use common::copy_fixture;
+
+#[test]
+fn finds_pages_containing_the_query() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    let nested = dir.path().join("pets");
+    std::fs::create_dir(&nested).map_err(|e| return e.to_string())?;
+    copy_fixture("three-pages.pdf", &dir.path().join("three-pages.pdf"))?;
+    copy_fixture("sections.pdf", &nested.join("sections.pdf"))?;
+
+    let filter = ScanFilter::new(dir.path(), ScanFilters::default());
+    let mut index = TextIndex::default();
+    index.update(dir.path(), &filter, &Passwords::default());
+
+    // Case is ignored and documents in sub folders are included
+    let hits = index.search(dir.path(), "MAX", 10);
+    let pages: Vec<i32> = hits.iter().map(|hit| return hit.page).collect();
+    assert_eq!(pages, vec![3, 4]);
+    assert_eq!(hits[0].name, "sections.pdf");
+    assert_eq!(hits[1].snippet, "Max vaccinations");
+
+    assert_eq!(index.search(dir.path(), "page  2", 10).len(), 1);
+    assert!(index.search(dir.path(), "Rex", 10).is_empty());
+    assert!(index
+        .search(&dir.path().join("other"), "Max", 10)
+        .is_empty());
+
+    return Ok(());
+}
+
+#[test]
+fn only_reindexes_changed_documents() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    copy_fixture("three-pages.pdf", &dir.path().join("three-pages.pdf"))?;
+    copy_fixture("one-page.pdf", &dir.path().join("one-page.pdf"))?;
+
+    let filter = ScanFilter::new(dir.path(), ScanFilters::default());
+    let mut index = TextIndex::default();
+    assert_eq!(
+        index
+            .update(dir.path(), &filter, &Passwords::default())
+            .indexed,
+        2
+    );
+    assert_eq!(
+        index.update(dir.path(), &filter, &Passwords::default()),
+        IndexUpdate::default()
+    );
+
+    std::fs::remove_file(dir.path().join("one-page.pdf")).map_err(|e| return e.to_string())?;
+    copy_fixture("sections.pdf", &dir.path().join("three-pages.pdf"))?;
+    let update = index.update(dir.path(), &filter, &Passwords::default());
+    assert_eq!(update.indexed, 1);
+    assert_eq!(update.removed, 1);
+    assert_eq!(index.search(dir.path(), "Bella", 10).len(), 1);
+
+    return Ok(());
+}
+
+#[test]
+fn keeps_the_text_of_unlocked_documents_off_disk() -> Result<(), String> {
+    let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+    copy_fixture("protected.pdf", &dir.path().join("protected.pdf"))?;
+    copy_fixture("one-page.pdf", &dir.path().join("one-page.pdf"))?;
+    let protected = dir.path().join("protected.pdf");
+    let passwords = Passwords::default();
+    passwords.unlock(&protected, "bella")?;
+
+    let filter = ScanFilter::new(dir.path(), ScanFilters::default());
+    let mut index = TextIndex::default();
+    assert_eq!(index.update(dir.path(), &filter, &passwords).indexed, 2);
+    let key = protected.to_string_lossy().to_string();
+    assert!(index
+        .documents
+        .get(&key)
+        .is_some_and(|document| return document.pages.len() == 2));
+
+    // Written out, only the unencrypted document is kept
+    let json = serde_json::to_string(&index).map_err(|e| return e.to_string())?;
+    let persisted: TextIndex = serde_json::from_str(&json).map_err(|e| return e.to_string())?;
+    assert!(!persisted.documents.contains_key(&key));
+    assert!(persisted.documents.contains_key(
+        &dir.path()
+            .join("one-page.pdf")
+            .to_string_lossy()
+            .to_string()
+    ));
+
+    return Ok(());
+}
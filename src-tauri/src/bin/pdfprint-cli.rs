@@ -0,0 +1,5 @@
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    return petprintpdfjs_lib::cli::main();
+}
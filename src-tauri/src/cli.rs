@@ -0,0 +1,352 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use pdfprint_core::blank::BlankOptions;
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf::{
+    self, CombineOptions, ImageFit, ImagePageSize, PdfDocument, PdfPrintDetails,
+};
+use pdfprint_core::printing::{self, PrintOptions, Sides};
+use pdfprint_core::range;
+
+/// How long `print --wait` waits for the printer to finish the job before giving up
+const WAIT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// How often `print --wait` checks whether the job is still in the printer's queue
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Combine and print documents without opening the app window
+#[derive(Parser)]
+#[command(name = "pdfprint-cli", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Args)]
+struct CombineArgs {
+    /// Page size for image and text inputs
+    #[arg(long, value_enum, default_value = "letter")]
+    image_page_size: PageSizeArg,
+
+    /// How image inputs are placed on their pages
+    #[arg(long, value_enum, default_value = "contain")]
+    image_fit: FitArg,
+
+    /// Leave out blank pages, such as the empty backs of scanned sheets
+    #[arg(long)]
+    skip_blank_pages: bool,
+
+    /// Largest share of a page, from 0 to 1, covered in ink for it to count as blank
+    #[arg(long, requires = "skip_blank_pages")]
+    blank_threshold: Option<f32>,
+
+    /// Documents to combine, optionally followed by a 1-based page range (`file.pdf:1-3,5`)
+    #[arg(required = true)]
+    inputs: Vec<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Combine documents into a single PDF
+    Combine {
+        /// File to write the combined PDF to
+        #[arg(long, short)]
+        out: PathBuf,
+
+        #[command(flatten)]
+        combine: CombineArgs,
+    },
+    /// Combine documents and send them to a printer
+    Print {
+        /// Printer to use instead of the default printer
+        #[arg(long)]
+        printer: Option<String>,
+
+        #[arg(long)]
+        copies: Option<u32>,
+
+        #[arg(long, value_enum)]
+        sides: Option<SidesArg>,
+
+        /// Print in monochrome
+        #[arg(long)]
+        monochrome: bool,
+
+        /// Name shown for the job in the printer queue
+        #[arg(long)]
+        job_name: Option<String>,
+
+        /// Wait until the printer has finished with the job
+        #[arg(long)]
+        wait: bool,
+
+        #[command(flatten)]
+        combine: CombineArgs,
+    },
+    /// List the available printers
+    ListPrinters,
+    /// Show details about a document
+    Info { file: PathBuf },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PageSizeArg {
+    Letter,
+    Legal,
+    A4,
+    Original,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FitArg {
+    Contain,
+    Cover,
+    Stretch,
+    Center,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SidesArg {
+    OneSided,
+    TwoSidedLongEdge,
+    TwoSidedShortEdge,
+}
+
+/// Failures mapped to distinct exit codes so scripts can react to them
+enum CliError {
+    /// An input could not be read or a page range is invalid
+    Input(String),
+    /// The output could not be written
+    Output(String),
+    /// The printer could not be found or refused the job
+    Printer(String),
+}
+
+impl CliError {
+    const fn exit_code(&self) -> u8 {
+        return match self {
+            Self::Input(_) => 3,
+            Self::Output(_) => 4,
+            Self::Printer(_) => 5,
+        };
+    }
+
+    fn message(&self) -> &str {
+        return match self {
+            Self::Input(message) | Self::Output(message) | Self::Printer(message) => message,
+        };
+    }
+}
+
+impl CombineArgs {
+    fn options(&self) -> CombineOptions {
+        return CombineOptions {
+            image_page_size: match self.image_page_size {
+                PageSizeArg::Letter => ImagePageSize::Letter,
+                PageSizeArg::Legal => ImagePageSize::Legal,
+                PageSizeArg::A4 => ImagePageSize::A4,
+                PageSizeArg::Original => ImagePageSize::Original,
+            },
+            image_fit: match self.image_fit {
+                FitArg::Contain => ImageFit::Contain,
+                FitArg::Cover => ImageFit::Cover,
+                FitArg::Stretch => ImageFit::Stretch,
+                FitArg::Center => ImageFit::Center,
+            },
+            skip_blank_pages: self.skip_blank_pages.then(|| {
+                return self
+                    .blank_threshold
+                    .map_or_else(BlankOptions::default, |threshold| {
+                        return BlankOptions { threshold };
+                    });
+            }),
+        };
+    }
+
+    fn combine(&self) -> Result<PdfDocument, CliError> {
+        // Nothing is unlocked from the command line, so password protected inputs are refused
+        let passwords = Passwords::default();
+        let documents = self
+            .inputs
+            .iter()
+            .map(|input| return parse_input(input, &passwords))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        return pdf::create_combined_pdf(documents, self.options(), &passwords)
+            .map_err(CliError::Input);
+    }
+}
+
+/// Splits `file.pdf:1-3` into the file and its page range, leaving paths such as `C:\file.pdf` alone
+fn split_input(input: &str) -> (&str, Option<&str>) {
+    return match input.rsplit_once(':') {
+        Some((path, spec)) if range::looks_like_range(spec) => (path, Some(spec)),
+        _ => (input, None),
+    };
+}
+
+fn parse_input(input: &str, passwords: &Passwords) -> Result<PdfPrintDetails, CliError> {
+    let (path, spec) = split_input(input);
+    let path = Path::new(path);
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| return CliError::Input(format!("{}: {e}", path.to_string_lossy())))?;
+    let pages = pdf::count_pages(path, passwords)
+        .map_err(|e| return CliError::Input(format!("{}: {e}", path.to_string_lossy())))?;
+    let print_range = spec
+        .map(|spec| return range::parse_page_range(spec, pages))
+        .transpose()
+        .map_err(|e| return CliError::Input(format!("{}: {e}", path.to_string_lossy())))?;
+
+    return Ok(PdfPrintDetails {
+        name: path.file_name().map_or_else(String::new, |name| {
+            return name.to_string_lossy().to_string();
+        }),
+        pages,
+        size: metadata.len(),
+        path: path.to_string_lossy().to_string(),
+        print_range,
+        collate: None,
+        select: None,
+        redactions: vec![],
+    });
+}
+
+fn write_line(line: &str) {
+    let _ = writeln!(std::io::stdout().lock(), "{line}");
+}
+
+fn combine(out: &Path, combine: &CombineArgs) -> Result<(), CliError> {
+    let document = combine.combine()?;
+
+    return pdf::save_document(&document, out)
+        .map_err(|e| return CliError::Output(format!("{}: {e}", out.to_string_lossy())));
+}
+
+fn print(
+    printer: Option<&str>,
+    options: &PrintOptions,
+    wait: bool,
+    combine: &CombineArgs,
+) -> Result<(), CliError> {
+    let document = combine.combine()?;
+    let printer = printing::find_printer(printer).map_err(CliError::Printer)?;
+    let job = printing::print_document(&printer, &document, options).map_err(CliError::Printer)?;
+
+    write_line(&format!("Sent job {job} to {}", printer.name));
+
+    if wait {
+        let started = Instant::now();
+        while printing::is_job_active(&printer, job) {
+            if started.elapsed() > WAIT_TIMEOUT {
+                return Err(CliError::Printer(format!(
+                    "Timed out waiting for job {job}"
+                )));
+            }
+            std::thread::sleep(WAIT_POLL_INTERVAL);
+        }
+    }
+
+    return Ok(());
+}
+
+fn list_printers() {
+    for printer in printing::list_printers() {
+        let marker = if printer.is_default { " (default)" } else { "" };
+        write_line(&format!("{}{marker}", printer.name));
+    }
+}
+
+fn info(file: &Path) -> Result<(), CliError> {
+    let metadata = std::fs::metadata(file)
+        .map_err(|e| return CliError::Input(format!("{}: {e}", file.to_string_lossy())))?;
+    let pages = pdf::count_pages(file, &Passwords::default())
+        .map_err(|e| return CliError::Input(format!("{}: {e}", file.to_string_lossy())))?;
+    let format = if pdf::is_image_path(file) {
+        "image"
+    } else if pdf::is_text_path(file) {
+        "text"
+    } else {
+        "pdf"
+    };
+
+    write_line(&format!("path: {}", file.to_string_lossy()));
+    write_line(&format!("format: {format}"));
+    write_line(&format!("pages: {pages}"));
+    write_line(&format!("size: {}", metadata.len()));
+
+    return Ok(());
+}
+
+/// Entry point for the `pdfprint-cli` binary
+pub fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match &cli.command {
+        Command::Combine { out, combine: args } => combine(out, args),
+        Command::Print {
+            printer,
+            copies,
+            sides,
+            monochrome,
+            job_name,
+            wait,
+            combine: args,
+        } => {
+            let options = PrintOptions {
+                copies: *copies,
+                sides: sides.map(|sides| {
+                    return match sides {
+                        SidesArg::OneSided => Sides::OneSided,
+                        SidesArg::TwoSidedLongEdge => Sides::TwoSidedLongEdge,
+                        SidesArg::TwoSidedShortEdge => Sides::TwoSidedShortEdge,
+                    };
+                }),
+                color: monochrome.then_some(false),
+                job_name: job_name.clone(),
+            };
+            print(printer.as_deref(), &options, *wait, args)
+        }
+        Command::ListPrinters => {
+            list_printers();
+            Ok(())
+        }
+        Command::Info { file } => info(file),
+    };
+
+    return match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            let _ = writeln!(std::io::stderr().lock(), "error: {}", err.message());
+            ExitCode::from(err.exit_code())
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_page_ranges_from_inputs() {
+        assert_eq!(split_input("file.pdf:1-3"), ("file.pdf", Some("1-3")));
+        assert_eq!(split_input("file.pdf:1-3,5"), ("file.pdf", Some("1-3,5")));
+        assert_eq!(split_input("file.pdf"), ("file.pdf", None));
+        assert_eq!(split_input("notes:draft.pdf"), ("notes:draft.pdf", None));
+    }
+
+    #[test]
+    fn keeps_windows_drive_paths_whole() {
+        assert_eq!(split_input(r"C:\file.pdf"), (r"C:\file.pdf", None));
+        assert_eq!(
+            split_input("C:/scans/file.pdf"),
+            ("C:/scans/file.pdf", None)
+        );
+        assert_eq!(
+            split_input(r"C:\file.pdf:2-4"),
+            (r"C:\file.pdf", Some("2-4"))
+        );
+    }
+}
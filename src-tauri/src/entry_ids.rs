@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use log::{error, info};
+use pdfprint_core::entry_id::EntryIds;
+use tauri::Manager;
+
+use crate::get_app_data_dir;
+use crate::mutex_utils::LockResultExt;
+use crate::persistence;
+
+const ENTRY_IDS_FILE: &str = "entry_ids.json";
+const ENTRY_IDS_VERSION: u32 = 1;
+
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+#[serde(default)]
+struct PinnedIds {
+    pinned: BTreeMap<String, u64>,
+}
+
+/// Restores the ids pinned after collisions in earlier sessions
+pub fn load(app_data: &Path) -> EntryIds {
+    let ids_json = app_data.join(ENTRY_IDS_FILE);
+
+    return match persistence::read_versioned::<PinnedIds>(&ids_json, ENTRY_IDS_VERSION, &[]) {
+        Ok(ids) => EntryIds::with_pinned(ids.unwrap_or_default().pinned),
+        Err(err) => {
+            error!("Could not load pinned entry ids: {err}");
+            if err.is_corrupt() {
+                if let Ok(backup) = persistence::backup_corrupt(&ids_json) {
+                    info!("Moved unreadable entry ids to {}", backup.to_string_lossy());
+                }
+            }
+            EntryIds::default()
+        }
+    };
+}
+
+/// Runs `assign` with the entry ids, saving any ids it pinned
+pub fn with_entry_ids<T, F>(app_handle: &tauri::AppHandle, assign: F) -> T
+where
+    F: FnOnce(&mut EntryIds) -> T,
+{
+    let ids_state = app_handle.state::<Mutex<EntryIds>>();
+    let mut ids = ids_state.lock().read_or_panic();
+    let result = assign(&mut ids);
+
+    if ids.take_pinned_changed() {
+        let saved = get_app_data_dir(app_handle).and_then(|app_data| {
+            return persistence::write_versioned(
+                &app_data.join(ENTRY_IDS_FILE),
+                ENTRY_IDS_VERSION,
+                &PinnedIds {
+                    pinned: ids.pinned().clone(),
+                },
+            );
+        });
+        if let Err(err) = saved {
+            error!("Could not save pinned entry ids: {err}");
+        }
+    }
+
+    return result;
+}
@@ -0,0 +1,331 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{error, info, warn};
+use pdfprint_core::file_utils;
+use pdfprint_core::pdf::{CombineOptions, PdfPrintDetails};
+use pdfprint_core::printing::PrintOptions;
+use tauri::Manager;
+
+use crate::get_app_data_dir;
+use crate::mutex_utils::LockResultExt;
+use crate::persistence;
+use crate::print_queue::PrintQueue;
+
+const HISTORY_FILE: &str = "print_history.json";
+const HISTORY_VERSION: u32 = 1;
+/// Oldest entries are dropped past this many
+const MAX_HISTORY_ENTRIES: usize = 5_000;
+
+/// Serializes read-modify-write cycles on the history file between threads, kept in the app
+/// state
+#[derive(Default)]
+pub struct HistoryStore {
+    lock: Mutex<()>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum HistoryStatus {
+    /// Handed to the printer, whether it finished printing is not known
+    Sent,
+    Completed,
+    Failed {
+        error: String,
+    },
+    /// Taken back out of the printer's queue before it finished
+    Cancelled,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct HistoryDocument {
+    #[serde(flatten)]
+    pub details: PdfPrintDetails,
+    /// Hash of the file when it was printed, unset when it could not be read
+    pub hash: Option<String>,
+    /// Hash of the separately scanned back sides, when the document was collated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backs_hash: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+    pub id: u64,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Unset when printing failed before a printer was found
+    pub printer: Option<String>,
+    pub print_options: PrintOptions,
+    pub combine_options: CombineOptions,
+    pub documents: Vec<HistoryDocument>,
+    /// Pages sent to the printer, including blank pages added to keep documents double sided
+    pub page_count: i32,
+    #[serde(flatten)]
+    pub status: HistoryStatus,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+#[serde(default)]
+struct History {
+    entries: Vec<HistoryEntry>,
+    next_id: u64,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceChange {
+    Modified,
+    Missing,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ChangedSource {
+    pub path: String,
+    pub change: SourceChange,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ReprintOutcome {
+    /// Print queue id of the reprint, unset when it was held back because sources changed
+    pub queued: Option<u64>,
+    pub changed: Vec<ChangedSource>,
+}
+
+fn now() -> u64 {
+    return SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| return duration.as_secs());
+}
+
+fn load(history_json: &Path) -> Result<History, String> {
+    return match persistence::read_versioned(history_json, HISTORY_VERSION, &[]) {
+        Ok(history) => Ok(history.unwrap_or_default()),
+        Err(err) => {
+            error!("Could not load print history: {err}");
+            if err.is_corrupt() {
+                if let Ok(backup) = persistence::backup_corrupt(history_json) {
+                    info!(
+                        "Moved unreadable print history to {}",
+                        backup.to_string_lossy()
+                    );
+                }
+            }
+            Err(format!("Could not load print history: {err}"))
+        }
+    };
+}
+
+fn modify<T, F>(app_handle: &tauri::AppHandle, update: F) -> Result<T, String>
+where
+    F: FnOnce(&mut History) -> Result<T, String>,
+{
+    let history_json = get_app_data_dir(app_handle)?.join(HISTORY_FILE);
+    let store = app_handle.state::<HistoryStore>();
+    let _guard = store.lock.lock().read_or_panic();
+
+    // Start over rather than stop recording when the file was corrupt, it has been backed up.
+    // Files that could not be read or are from a newer version are refused by the write.
+    let mut history = load(&history_json).unwrap_or_default();
+    let result = update(&mut history)?;
+    let excess = history.entries.len().saturating_sub(MAX_HISTORY_ENTRIES);
+    history.entries.drain(..excess);
+    persistence::write_versioned(&history_json, HISTORY_VERSION, &history)?;
+
+    return Ok(result);
+}
+
+fn hash(path: &str) -> Option<String> {
+    return file_utils::hash_file(Path::new(path))
+        .map_err(|err| {
+            warn!("Could not hash {path}: {err}");
+        })
+        .ok();
+}
+
+/// Hashes the documents about to be printed, so a reprint can tell whether they changed
+pub fn snapshot_documents(documents: &[PdfPrintDetails]) -> Vec<HistoryDocument> {
+    return documents
+        .iter()
+        .map(|details| {
+            return HistoryDocument {
+                details: details.clone(),
+                hash: hash(&details.path),
+                backs_hash: details
+                    .collate
+                    .as_ref()
+                    .and_then(|collate| return hash(&collate.backs)),
+            };
+        })
+        .collect();
+}
+
+/// Adds an entry to the history, returning its id. Failures are logged rather than
+/// returned since they should never stop a print.
+pub fn record(
+    app_handle: &tauri::AppHandle,
+    printer: Option<String>,
+    documents: Vec<HistoryDocument>,
+    combine_options: CombineOptions,
+    print_options: &PrintOptions,
+    page_count: i32,
+    status: HistoryStatus,
+) -> Option<u64> {
+    let recorded = modify(app_handle, |history| {
+        history.next_id += 1;
+        let id = history.next_id;
+        history.entries.push(HistoryEntry {
+            id,
+            timestamp: now(),
+            printer,
+            print_options: print_options.clone(),
+            combine_options,
+            documents,
+            page_count,
+            status,
+        });
+        return Ok(id);
+    });
+
+    return recorded
+        .map_err(|err| {
+            error!("Could not record print history: {err}");
+        })
+        .ok();
+}
+
+/// Updates the status of an entry once the printer has finished with it
+pub fn set_status(app_handle: &tauri::AppHandle, id: u64, status: HistoryStatus) {
+    let updated = modify(app_handle, |history| {
+        if let Some(entry) = history
+            .entries
+            .iter_mut()
+            .find(|entry| return entry.id == id)
+        {
+            entry.status = status;
+        }
+        return Ok(());
+    });
+
+    if let Err(err) = updated {
+        error!("Could not update print history: {err}");
+    }
+}
+
+fn changed_source(path: &str, hash: Option<&String>) -> Option<ChangedSource> {
+    let file = Path::new(path);
+    let change = if !file.is_file() {
+        SourceChange::Missing
+    } else if file_utils::hash_file(file).ok().as_ref() != hash {
+        SourceChange::Modified
+    } else {
+        return None;
+    };
+
+    return Some(ChangedSource {
+        path: path.to_string(),
+        change,
+    });
+}
+
+fn changed_sources(entry: &HistoryEntry) -> Vec<ChangedSource> {
+    return entry
+        .documents
+        .iter()
+        .flat_map(|document| {
+            let backs = document.details.collate.as_ref().and_then(|collate| {
+                return changed_source(&collate.backs, document.backs_hash.as_ref());
+            });
+            return changed_source(&document.details.path, document.hash.as_ref())
+                .into_iter()
+                .chain(backs);
+        })
+        .collect();
+}
+
+/// Lists entries newest first, optionally only those between `from` and `to` (seconds since
+/// the Unix epoch, inclusive) and those printing a file whose path contains `file`
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+pub fn list_print_history(
+    app_handle: tauri::AppHandle,
+    from: Option<u64>,
+    to: Option<u64>,
+    file: Option<String>,
+) -> Result<Vec<HistoryEntry>, String> {
+    let history_json = get_app_data_dir(&app_handle)?.join(HISTORY_FILE);
+    let history = {
+        let store = app_handle.state::<HistoryStore>();
+        let _guard = store.lock.lock().read_or_panic();
+        load(&history_json)?
+    };
+    let file = file.map(|file| return file.to_lowercase());
+
+    return Ok(history
+        .entries
+        .into_iter()
+        .rev()
+        .filter(|entry| return from.is_none_or(|from| return entry.timestamp >= from))
+        .filter(|entry| return to.is_none_or(|to| return entry.timestamp <= to))
+        .filter(|entry| {
+            return file.as_ref().is_none_or(|file| {
+                return entry
+                    .documents
+                    .iter()
+                    .any(|document| return document.details.path.to_lowercase().contains(file));
+            });
+        })
+        .collect());
+}
+
+/// Queues an entry to print again. When a source file changed since it was printed, nothing
+/// is queued unless `confirm_changed` is set, so the user can be warned first. Missing
+/// files can not be printed at all.
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+pub fn reprint_history_entry(
+    app_handle: tauri::AppHandle,
+    id: u64,
+    confirm_changed: bool,
+) -> Result<ReprintOutcome, String> {
+    let history_json = get_app_data_dir(&app_handle)?.join(HISTORY_FILE);
+    let entry = {
+        let store = app_handle.state::<HistoryStore>();
+        let _guard = store.lock.lock().read_or_panic();
+        load(&history_json)?
+    }
+    .entries
+    .into_iter()
+    .find(|entry| return entry.id == id)
+    .ok_or_else(|| return format!("Unknown print history entry: {id}"))?;
+
+    let changed = changed_sources(&entry);
+    if let Some(missing) = changed
+        .iter()
+        .find(|source| return matches!(source.change, SourceChange::Missing))
+    {
+        return Err(format!("{} no longer exists", missing.path));
+    }
+    if !changed.is_empty() && !confirm_changed {
+        return Ok(ReprintOutcome {
+            queued: None,
+            changed,
+        });
+    }
+
+    let queued = app_handle.state::<Arc<PrintQueue>>().enqueue(
+        entry.printer.as_deref(),
+        entry
+            .documents
+            .into_iter()
+            .map(|document| return document.details)
+            .collect(),
+        entry.combine_options,
+        entry.print_options,
+    )?;
+
+    return Ok(ReprintOutcome {
+        queued: Some(queued),
+        changed,
+    });
+}
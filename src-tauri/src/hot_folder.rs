@@ -0,0 +1,516 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::{error, info, warn};
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf::{self, PdfPrintDetails};
+use pdfprint_core::printing::PrintOptions;
+use pdfprint_core::scan_filter::{Accepted, ScanFilter, ScanFilters};
+use tauri::{Emitter, Manager};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::mutex_utils::LockResultExt;
+use crate::print_queue::{PrintQueue, QueuedJobStatus};
+use crate::settings::HotFolderSettings;
+
+const DONE_DIR: &str = "done";
+const FAILED_DIR: &str = "failed";
+const LOG_FILE: &str = "hot-folder.log";
+/// Only PDFs saved into the folder are printed, images and text are left alone
+const PRINTED_EXTENSIONS: [&str; 1] = ["pdf"];
+
+#[derive(serde::Serialize, Clone)]
+struct HotFolderProcessedPayload {
+    name: String,
+    destination: String,
+    error: Option<String>,
+}
+
+/// Size and modification time of a file seen while polling, with how many polls it stayed unchanged
+struct Observed {
+    size: u64,
+    modified: Option<SystemTime>,
+    unchanged: u32,
+}
+
+/// How a file stands after a poll found it at `size` and `modified`, counting the polls in a
+/// row it was found the same. A file seen for the first time has not stayed the same yet.
+fn observe(previous: Option<&Observed>, size: u64, modified: Option<SystemTime>) -> Observed {
+    let unchanged = match previous {
+        Some(previous) if previous.size == size && previous.modified == modified => {
+            previous.unchanged + 1
+        }
+        _ => 0,
+    };
+
+    return Observed {
+        size,
+        modified,
+        unchanged,
+    };
+}
+
+struct Running {
+    stop: Sender<()>,
+    thread: JoinHandle<()>,
+    settings: HotFolderSettings,
+}
+
+/// Background poller printing files saved into a watched folder
+#[derive(Default)]
+pub struct HotFolder {
+    running: Mutex<Option<Running>>,
+}
+
+fn timestamp() -> String {
+    return OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .unwrap_or_default();
+}
+
+/// Appends a line to the log file kept in the hot folder, next to `done/` and `failed/`
+fn append_log(folder: &Path, line: &str) {
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(folder.join(LOG_FILE))
+        .and_then(|mut file| return writeln!(file, "{} {line}", timestamp()));
+    if let Err(err) = written {
+        warn!("Could not write the hot folder log: {err}");
+    }
+}
+
+/// Picks a name in `dir` that does not exist yet, adding a counter before the extension
+fn unique_destination(dir: &Path, name: &str) -> PathBuf {
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let path = Path::new(name);
+    let stem = path.file_stem().map_or_else(String::new, |stem| {
+        return stem.to_string_lossy().to_string();
+    });
+    let extension = path
+        .extension()
+        .map(|ext| return format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut counter = 1;
+    loop {
+        let candidate = dir.join(format!("{stem} ({counter}){extension}"));
+        if !candidate.exists() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Adds a stable file to the print queue, returning the queue id of its job
+fn queue_file(
+    app_handle: &tauri::AppHandle,
+    path: &Path,
+    size: u64,
+    settings: &HotFolderSettings,
+) -> Result<u64, String> {
+    let name = path.file_name().map_or_else(String::new, |name| {
+        return name.to_string_lossy().to_string();
+    });
+    let document = PdfPrintDetails {
+        name: name.clone(),
+        pages: pdf::count_pages(path, &app_handle.state::<Passwords>())?,
+        size,
+        path: path.to_string_lossy().to_string(),
+        print_range: None,
+        collate: None,
+        select: None,
+        redactions: vec![],
+    };
+    let print_options = PrintOptions {
+        job_name: settings.print_options.job_name.clone().or(Some(name)),
+        ..settings.print_options.clone()
+    };
+
+    return app_handle.state::<Arc<PrintQueue>>().enqueue(
+        settings.printer.as_deref(),
+        vec![document],
+        settings.combine_options,
+        print_options,
+    );
+}
+
+/// Where a file goes once its job settled, with the line to log and the error to report, or
+/// nothing when the job has not settled. Jobs that timed out were sent and may well have
+/// printed, so they count as done.
+fn route(
+    name: &str,
+    outcome: Result<QueuedJobStatus, String>,
+) -> Option<(&'static str, String, Option<String>)> {
+    return match outcome {
+        Ok(QueuedJobStatus::Completed) => Some((DONE_DIR, format!("PRINTED {name}"), None)),
+        Ok(QueuedJobStatus::TimedOut) => Some((
+            DONE_DIR,
+            format!("UNCONFIRMED {name}: the printer did not report the job finished"),
+            None,
+        )),
+        Ok(QueuedJobStatus::Cancelled) => Some((
+            FAILED_DIR,
+            format!("CANCELLED {name}"),
+            Some("Cancelled".to_string()),
+        )),
+        Ok(QueuedJobStatus::Failed { error }) | Err(error) => {
+            Some((FAILED_DIR, format!("FAILED {name}: {error}"), Some(error)))
+        }
+        Ok(status) => {
+            warn!("Hot folder job for {name} has not settled: {status:?}");
+            None
+        }
+    };
+}
+
+/// Moves a file whose job settled to `done/` or `failed/`, returning whether it was moved
+fn file_away(
+    app_handle: &tauri::AppHandle,
+    folder: &Path,
+    path: &Path,
+    outcome: Result<QueuedJobStatus, String>,
+) -> bool {
+    let name = path.file_name().map_or_else(String::new, |name| {
+        return name.to_string_lossy().to_string();
+    });
+
+    let Some((subfolder, line, error)) = route(&name, outcome) else {
+        return false;
+    };
+
+    let moved = std::fs::create_dir_all(folder.join(subfolder))
+        .and_then(|()| {
+            let destination = unique_destination(&folder.join(subfolder), &name);
+            return std::fs::rename(path, &destination).map(|()| return destination);
+        })
+        .map_err(|e| return e.to_string());
+
+    let destination = match moved {
+        Ok(destination) => destination,
+        Err(err) => {
+            error!("Could not move {name} out of the hot folder: {err}");
+            append_log(
+                folder,
+                &format!("ERROR {name}: could not move to {subfolder}/: {err}"),
+            );
+            return false;
+        }
+    };
+
+    match &error {
+        None => info!("Hot folder printed {name}"),
+        Some(err) => error!("Hot folder could not print {name}: {err}"),
+    }
+    append_log(folder, &line);
+
+    let _ = app_handle.emit(
+        "hot-folder-processed",
+        HotFolderProcessedPayload {
+            name,
+            destination: destination.to_string_lossy().to_string(),
+            error,
+        },
+    );
+
+    return true;
+}
+
+/// Files in the folder whose jobs are still in the queue, including ones queued before
+/// the app or the hot folder was restarted
+fn queued_files(queue: &PrintQueue, folder: &Path) -> HashMap<PathBuf, u64> {
+    return queue
+        .snapshot()
+        .jobs
+        .into_iter()
+        .filter(|job| return !job.status.is_settled())
+        .flat_map(|job| {
+            return job
+                .documents
+                .into_iter()
+                .map(move |document| return (PathBuf::from(document.path), job.id));
+        })
+        .filter(|(path, _)| return path.parent() == Some(folder))
+        .collect();
+}
+
+/// Files still in the folder whose last job settled while the hot folder was not watching, such
+/// as jobs interrupted when the app closed, with how that job ended. A file changed since its
+/// job was submitted was saved into the folder again and is left to be printed as a new file.
+fn settled_files(queue: &PrintQueue, folder: &Path) -> Vec<(PathBuf, QueuedJobStatus)> {
+    let mut last_jobs: HashMap<PathBuf, (u64, u64, QueuedJobStatus)> = HashMap::new();
+    for job in queue.snapshot().jobs {
+        for document in job.documents {
+            let path = PathBuf::from(document.path);
+            if path.parent() != Some(folder) {
+                continue;
+            }
+            if last_jobs
+                .get(&path)
+                .is_none_or(|(id, _, _)| return *id < job.id)
+            {
+                last_jobs.insert(path, (job.id, job.submitted_at, job.status.clone()));
+            }
+        }
+    }
+
+    return last_jobs
+        .into_iter()
+        .filter(|(_, (_, _, status))| return status.is_settled())
+        .filter(|(path, (_, submitted_at, _))| {
+            return std::fs::metadata(path)
+                .and_then(|metadata| return metadata.modified())
+                .ok()
+                .and_then(|modified| return modified.duration_since(UNIX_EPOCH).ok())
+                .is_some_and(|modified| return modified.as_secs() <= *submitted_at);
+        })
+        .map(|(path, (_, _, status))| return (path, status))
+        .collect();
+}
+
+/// Lists the PDFs directly inside the folder, skipping `done/` and `failed/`
+fn list_candidates(folder: &Path, filter: &ScanFilter) -> Vec<(PathBuf, std::fs::Metadata)> {
+    let entries = match std::fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!("Could not read the hot folder: {err}");
+            return vec![];
+        }
+    };
+
+    return entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            return match filter.accept(&entry)? {
+                Accepted::File(metadata) => Some((entry.path(), metadata)),
+                Accepted::Dir => None,
+            };
+        })
+        .collect();
+}
+
+/// Polls the folder until `stop` fires, queueing files once their size and modification
+/// time stayed the same for `stable_checks` polls in a row. Files stay in the folder until
+/// their job settles.
+fn watch(
+    app_handle: &tauri::AppHandle,
+    folder: &Path,
+    settings: &HotFolderSettings,
+    stop: &mpsc::Receiver<()>,
+) {
+    let queue = app_handle.state::<Arc<PrintQueue>>();
+    let filter = ScanFilter::new(
+        folder,
+        ScanFilters {
+            extensions: PRINTED_EXTENSIONS.map(str::to_string).to_vec(),
+            ..ScanFilters::default()
+        },
+    );
+    let interval = Duration::from_secs(settings.poll_seconds.max(1));
+    let mut observed: HashMap<PathBuf, Observed> = HashMap::new();
+    let mut queued = queued_files(&queue, folder);
+    // Files that could not be moved away, which would otherwise print again on every poll
+    let mut stuck: HashSet<PathBuf> = HashSet::new();
+
+    // Their jobs may have partly printed, so they are filed away rather than printed again
+    for (path, status) in settled_files(&queue, folder) {
+        if !file_away(app_handle, folder, &path, Ok(status)) {
+            stuck.insert(path);
+        }
+    }
+
+    loop {
+        match stop.recv_timeout(interval) {
+            Err(RecvTimeoutError::Timeout) => {}
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        queued.retain(|path, id| {
+            let outcome = match queue.status(*id) {
+                Some(status) if !status.is_settled() => return true,
+                Some(status) => Ok(status),
+                None => Err("The job was removed from the print queue".to_string()),
+            };
+            if !file_away(app_handle, folder, path, outcome) {
+                stuck.insert(path.clone());
+            }
+            return false;
+        });
+
+        let candidates = list_candidates(folder, &filter);
+        observed.retain(|path, _| return candidates.iter().any(|(p, _)| return p == path));
+        stuck.retain(|path| return candidates.iter().any(|(p, _)| return p == path));
+
+        for (path, metadata) in candidates {
+            if stuck.contains(&path) || queued.contains_key(&path) {
+                continue;
+            }
+
+            let size = metadata.len();
+            let current = observe(observed.get(&path), size, metadata.modified().ok());
+            if current.unchanged < settings.stable_checks {
+                observed.insert(path, current);
+            } else {
+                observed.remove(&path);
+                match queue_file(app_handle, &path, size, settings) {
+                    Ok(id) => {
+                        info!("Hot folder queued {}", path.to_string_lossy());
+                        queued.insert(path, id);
+                    }
+                    Err(err) => {
+                        if !file_away(app_handle, folder, &path, Err(err)) {
+                            stuck.insert(path);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl HotFolder {
+    fn stop(&self) {
+        let running = self.running.lock().read_or_panic().take();
+        if let Some(running) = running {
+            let _ = running.stop.send(());
+            if running.thread.join().is_err() {
+                error!("Hot folder thread panicked");
+            }
+            info!("Hot folder stopped");
+        }
+    }
+
+    /// Starts, restarts or stops watching so it matches the settings
+    pub fn configure(
+        &self,
+        app_handle: &tauri::AppHandle,
+        settings: &HotFolderSettings,
+    ) -> Result<(), String> {
+        let unchanged = self
+            .running
+            .lock()
+            .read_or_panic()
+            .as_ref()
+            .is_some_and(|running| return &running.settings == settings);
+        if unchanged {
+            return Ok(());
+        }
+
+        self.stop();
+
+        if !settings.enabled {
+            return Ok(());
+        }
+        let folder = PathBuf::from(
+            settings
+                .path
+                .as_ref()
+                .ok_or_else(|| return "The hot folder needs a path".to_string())?,
+        );
+        if !folder.is_dir() {
+            return Err(format!(
+                "Hot folder {} is not a directory",
+                folder.to_string_lossy()
+            ));
+        }
+
+        let (stop, stop_rx) = mpsc::channel();
+        let handle = app_handle.clone();
+        let thread_settings = settings.clone();
+        let thread_folder = folder.clone();
+        let thread = std::thread::spawn(move || {
+            watch(&handle, &thread_folder, &thread_settings, &stop_rx);
+        });
+
+        info!("Watching hot folder {}", folder.to_string_lossy());
+        append_log(&folder, "STARTED");
+        *self.running.lock().read_or_panic() = Some(Running {
+            stop,
+            thread,
+            settings: settings.clone(),
+        });
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_polls_a_file_stayed_the_same() {
+        let modified = Some(SystemTime::UNIX_EPOCH);
+
+        let first = observe(None, 10, modified);
+        assert_eq!(first.unchanged, 0);
+        let second = observe(Some(&first), 10, modified);
+        assert_eq!(second.unchanged, 1);
+        let third = observe(Some(&second), 10, modified);
+        assert_eq!(third.unchanged, 2);
+
+        // Still being written, so it starts over
+        assert_eq!(observe(Some(&third), 20, modified).unchanged, 0);
+        assert_eq!(observe(Some(&third), 10, None).unchanged, 0);
+    }
+
+    #[test]
+    fn numbers_destinations_that_are_taken() -> Result<(), String> {
+        let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+        assert_eq!(
+            unique_destination(dir.path(), "scan.pdf"),
+            dir.path().join("scan.pdf")
+        );
+
+        for name in ["scan.pdf", "scan (1).pdf", "notes"] {
+            std::fs::write(dir.path().join(name), "").map_err(|e| return e.to_string())?;
+        }
+        assert_eq!(
+            unique_destination(dir.path(), "scan.pdf"),
+            dir.path().join("scan (2).pdf")
+        );
+        assert_eq!(
+            unique_destination(dir.path(), "notes"),
+            dir.path().join("notes (1)")
+        );
+
+        return Ok(());
+    }
+
+    #[test]
+    fn routes_settled_jobs() {
+        let folder = |outcome| return route("scan.pdf", outcome).map(|(dir, _, _)| return dir);
+
+        assert_eq!(folder(Ok(QueuedJobStatus::Completed)), Some(DONE_DIR));
+        assert_eq!(folder(Ok(QueuedJobStatus::TimedOut)), Some(DONE_DIR));
+        assert_eq!(folder(Ok(QueuedJobStatus::Cancelled)), Some(FAILED_DIR));
+        assert_eq!(
+            folder(Ok(QueuedJobStatus::Failed {
+                error: "jammed".to_string()
+            })),
+            Some(FAILED_DIR)
+        );
+        assert_eq!(folder(Err("removed".to_string())), Some(FAILED_DIR));
+        assert_eq!(folder(Ok(QueuedJobStatus::Printing)), None);
+
+        assert_eq!(
+            route("scan.pdf", Err("removed".to_string())),
+            Some((
+                FAILED_DIR,
+                "FAILED scan.pdf: removed".to_string(),
+                Some("removed".to_string())
+            ))
+        );
+    }
+}
@@ -0,0 +1,427 @@
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use log::{error, info};
+
+use pdfprint_core::pdf::{Collate, CombineOptions, PdfPrintDetails};
+use pdfprint_core::printing::PrintOptions;
+use tauri::Manager;
+
+use crate::mutex_utils::LockResultExt;
+use crate::persistence;
+use crate::print_queue::PrintQueue;
+use crate::{get_app_data_dir, get_workspace_root};
+
+const SAVED_JOBS_FILE: &str = "saved_jobs.json";
+const SAVED_JOBS_VERSION: u32 = 1;
+
+/// Serializes the load, modify, save cycles on the saved jobs file, kept in the app state
+#[derive(Default)]
+pub struct SavedJobsStore {
+    lock: Mutex<()>,
+}
+
+/// A named selection of documents that can be printed again later
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct SavedJob {
+    pub id: u64,
+    pub name: String,
+    /// Documents to print, with paths relative to the workspace root
+    pub documents: Vec<PdfPrintDetails>,
+    #[serde(default)]
+    pub combine_options: CombineOptions,
+    #[serde(default)]
+    pub print_options: PrintOptions,
+    /// Printer to use, the default printer when unset
+    pub printer: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+#[serde(default)]
+struct SavedJobs {
+    next_id: u64,
+    jobs: Vec<SavedJob>,
+}
+
+/// A saved job along with the documents that do not resolve in the current workspace
+#[derive(serde::Serialize)]
+pub struct SavedJobStatus {
+    #[serde(flatten)]
+    job: SavedJob,
+    invalid_paths: Vec<String>,
+}
+
+fn load(jobs_json: &Path) -> Result<SavedJobs, String> {
+    return match persistence::read_versioned(jobs_json, SAVED_JOBS_VERSION, &[]) {
+        Ok(jobs) => Ok(jobs.unwrap_or_default()),
+        Err(err) => {
+            error!("Could not load saved jobs: {err}");
+            if err.is_corrupt() {
+                if let Ok(backup) = persistence::backup_corrupt(jobs_json) {
+                    info!(
+                        "Moved unreadable saved jobs to {}",
+                        backup.to_string_lossy()
+                    );
+                }
+            }
+            Err(format!("Could not load saved jobs: {err}"))
+        }
+    };
+}
+
+/// Runs `update` against the stored jobs and writes them back
+fn modify<T, F>(app_handle: &tauri::AppHandle, update: F) -> Result<T, String>
+where
+    F: FnOnce(&mut SavedJobs) -> Result<T, String>,
+{
+    let jobs_json = get_app_data_dir(app_handle)?.join(SAVED_JOBS_FILE);
+    let store = app_handle.state::<SavedJobsStore>();
+    let _guard = store.lock.lock().read_or_panic();
+
+    let mut saved_jobs = load(&jobs_json)?;
+    let result = update(&mut saved_jobs)?;
+    persistence::write_versioned(&jobs_json, SAVED_JOBS_VERSION, &saved_jobs)?;
+
+    return Ok(result);
+}
+
+/// Resolves a stored path against the workspace, refusing anything that escapes it
+fn resolve(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    let relative_path = Path::new(relative);
+    let escapes = relative_path.is_absolute()
+        || relative_path
+            .components()
+            .any(|component| return matches!(component, Component::ParentDir));
+    if escapes {
+        return Err(format!("{relative} is not inside the workspace"));
+    }
+
+    let resolved = root.join(relative_path);
+    if !resolved.is_file() {
+        return Err(format!("{relative} does not exist in the workspace"));
+    }
+
+    return Ok(resolved);
+}
+
+/// Makes a document path relative to the workspace, checking it exists there
+fn relative_path(root: &Path, document_path: &str) -> Result<String, String> {
+    let path = Path::new(document_path);
+    let relative = if path.is_absolute() {
+        path.strip_prefix(root)
+            .map_err(|_| return format!("{document_path} is not inside the workspace"))?
+            .to_path_buf()
+    } else {
+        path.to_path_buf()
+    };
+    resolve(root, &relative.to_string_lossy())?;
+
+    return Ok(relative.to_string_lossy().to_string());
+}
+
+fn to_relative(
+    root: &Path,
+    documents: Vec<PdfPrintDetails>,
+) -> Result<Vec<PdfPrintDetails>, String> {
+    return documents
+        .into_iter()
+        .map(|document| -> Result<PdfPrintDetails, String> {
+            let collate = document
+                .collate
+                .map(|collate| -> Result<Collate, String> {
+                    return Ok(Collate {
+                        backs: relative_path(root, &collate.backs)?,
+                        ..collate
+                    });
+                })
+                .transpose()?;
+
+            return Ok(PdfPrintDetails {
+                path: relative_path(root, &document.path)?,
+                collate,
+                ..document
+            });
+        })
+        .collect();
+}
+
+/// Resolves the stored documents of a job against the workspace they are printed from
+fn to_absolute(
+    root: &Path,
+    documents: Vec<PdfPrintDetails>,
+) -> Result<Vec<PdfPrintDetails>, String> {
+    return documents
+        .into_iter()
+        .map(|document| -> Result<PdfPrintDetails, String> {
+            let resolved = resolve(root, &document.path)?;
+            let collate = document
+                .collate
+                .map(|collate| -> Result<Collate, String> {
+                    let backs = resolve(root, &collate.backs)?;
+                    return Ok(Collate {
+                        backs: backs.to_string_lossy().to_string(),
+                        ..collate
+                    });
+                })
+                .transpose()?;
+
+            return Ok(PdfPrintDetails {
+                path: resolved.to_string_lossy().to_string(),
+                collate,
+                ..document
+            });
+        })
+        .collect();
+}
+
+/// Stored paths of a document, including the back sides it is collated with
+fn document_paths(document: &PdfPrintDetails) -> impl Iterator<Item = &String> {
+    return std::iter::once(&document.path).chain(
+        document
+            .collate
+            .as_ref()
+            .map(|collate| return &collate.backs),
+    );
+}
+
+fn invalid_paths(root: Option<&Path>, job: &SavedJob) -> Vec<String> {
+    return job
+        .documents
+        .iter()
+        .flat_map(document_paths)
+        .filter(|path| {
+            return root.is_none_or(|root| return resolve(root, path).is_err());
+        })
+        .cloned()
+        .collect();
+}
+
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+pub fn list_saved_jobs(app_handle: tauri::AppHandle) -> Result<Vec<SavedJobStatus>, String> {
+    let jobs_json = get_app_data_dir(&app_handle)?.join(SAVED_JOBS_FILE);
+    let root = get_workspace_root(&app_handle).ok();
+    let saved_jobs = {
+        let store = app_handle.state::<SavedJobsStore>();
+        let _guard = store.lock.lock().read_or_panic();
+        load(&jobs_json)?
+    };
+
+    return Ok(saved_jobs
+        .jobs
+        .into_iter()
+        .map(|job| {
+            let invalid_paths = invalid_paths(root.as_deref().map(Path::new), &job);
+            return SavedJobStatus { job, invalid_paths };
+        })
+        .collect());
+}
+
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+pub fn create_saved_job(
+    app_handle: tauri::AppHandle,
+    name: String,
+    pdfs: Vec<PdfPrintDetails>,
+    combine_options: Option<CombineOptions>,
+    print_options: Option<PrintOptions>,
+    printer: Option<String>,
+) -> Result<SavedJob, String> {
+    let root = get_workspace_root(&app_handle)?;
+    let documents = to_relative(Path::new(&root), pdfs)?;
+
+    return modify(&app_handle, |saved_jobs| {
+        let job = SavedJob {
+            id: saved_jobs.next_id,
+            name,
+            documents,
+            combine_options: combine_options.unwrap_or_default(),
+            print_options: print_options.unwrap_or_default(),
+            printer,
+        };
+        saved_jobs.next_id += 1;
+        saved_jobs.jobs.push(job.clone());
+
+        return Ok(job);
+    });
+}
+
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+pub fn update_saved_job(
+    app_handle: tauri::AppHandle,
+    id: u64,
+    name: String,
+    pdfs: Vec<PdfPrintDetails>,
+    combine_options: Option<CombineOptions>,
+    print_options: Option<PrintOptions>,
+    printer: Option<String>,
+) -> Result<SavedJob, String> {
+    let root = get_workspace_root(&app_handle)?;
+    let documents = to_relative(Path::new(&root), pdfs)?;
+
+    return modify(&app_handle, |saved_jobs| {
+        let job = saved_jobs
+            .jobs
+            .iter_mut()
+            .find(|job| return job.id == id)
+            .ok_or_else(|| return format!("Unknown saved job: {id}"))?;
+
+        *job = SavedJob {
+            id,
+            name,
+            documents,
+            combine_options: combine_options.unwrap_or_default(),
+            print_options: print_options.unwrap_or_default(),
+            printer,
+        };
+
+        return Ok(job.clone());
+    });
+}
+
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+pub fn delete_saved_job(app_handle: tauri::AppHandle, id: u64) -> Result<(), String> {
+    return modify(&app_handle, |saved_jobs| {
+        let before = saved_jobs.jobs.len();
+        saved_jobs.jobs.retain(|job| return job.id != id);
+        if saved_jobs.jobs.len() == before {
+            return Err(format!("Unknown saved job: {id}"));
+        }
+
+        return Ok(());
+    });
+}
+
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+pub fn run_saved_job(app_handle: tauri::AppHandle, id: u64) -> Result<(), String> {
+    let jobs_json = get_app_data_dir(&app_handle)?.join(SAVED_JOBS_FILE);
+    let root = get_workspace_root(&app_handle)?;
+    let job = {
+        let store = app_handle.state::<SavedJobsStore>();
+        let _guard = store.lock.lock().read_or_panic();
+        load(&jobs_json)?
+    }
+    .jobs
+    .into_iter()
+    .find(|job| return job.id == id)
+    .ok_or_else(|| return format!("Unknown saved job: {id}"))?;
+
+    let documents = to_absolute(Path::new(&root), job.documents)?;
+
+    info!("Running saved job {}", job.name);
+
+    let print_options = PrintOptions {
+        job_name: job.print_options.job_name.clone().or(Some(job.name)),
+        ..job.print_options
+    };
+    app_handle.state::<Arc<PrintQueue>>().enqueue(
+        job.printer.as_deref(),
+        documents,
+        job.combine_options,
+        print_options,
+    )?;
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(path: &str, backs: Option<&str>) -> PdfPrintDetails {
+        return PdfPrintDetails {
+            name: "scan.pdf".to_string(),
+            pages: 1,
+            size: 0,
+            path: path.to_string(),
+            print_range: None,
+            collate: backs.map(|backs| {
+                return Collate {
+                    backs: backs.to_string(),
+                    reverse_backs: false,
+                };
+            }),
+            select: None,
+            redactions: vec![],
+        };
+    }
+
+    fn workspace() -> Result<tempfile::TempDir, String> {
+        let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+        std::fs::create_dir(dir.path().join("pets")).map_err(|e| return e.to_string())?;
+        for name in ["pets/scan.pdf", "pets/backs.pdf"] {
+            std::fs::write(dir.path().join(name), "").map_err(|e| return e.to_string())?;
+        }
+        return Ok(dir);
+    }
+
+    #[test]
+    fn resolves_documents_inside_the_workspace() -> Result<(), String> {
+        let dir = workspace()?;
+        let root = dir.path();
+
+        assert_eq!(resolve(root, "pets/scan.pdf")?, root.join("pets/scan.pdf"));
+        assert!(resolve(root, "pets/missing.pdf").is_err());
+        assert!(resolve(root, "../scan.pdf").is_err());
+        let absolute = root.join("pets/scan.pdf").to_string_lossy().to_string();
+        assert!(resolve(root, &absolute).is_err());
+
+        return Ok(());
+    }
+
+    #[test]
+    fn stores_paths_relative_to_the_workspace() -> Result<(), String> {
+        let dir = workspace()?;
+        let root = dir.path();
+        let absolute = root.join("pets/scan.pdf").to_string_lossy().to_string();
+
+        assert_eq!(
+            PathBuf::from(relative_path(root, &absolute)?),
+            Path::new("pets").join("scan.pdf")
+        );
+        assert_eq!(
+            PathBuf::from(relative_path(root, "pets/scan.pdf")?),
+            Path::new("pets").join("scan.pdf")
+        );
+        assert!(relative_path(root, "pets/missing.pdf").is_err());
+
+        let other = tempfile::tempdir().map_err(|e| return e.to_string())?;
+        let outside = other.path().join("scan.pdf");
+        std::fs::write(&outside, "").map_err(|e| return e.to_string())?;
+        assert!(relative_path(root, &outside.to_string_lossy()).is_err());
+
+        return Ok(());
+    }
+
+    #[test]
+    fn resolves_saved_documents_for_printing() -> Result<(), String> {
+        let dir = workspace()?;
+        let root = dir.path();
+
+        let documents = to_absolute(
+            root,
+            vec![document("pets/scan.pdf", Some("pets/backs.pdf"))],
+        )?;
+        assert_eq!(
+            Path::new(&documents[0].path),
+            root.join("pets/scan.pdf").as_path()
+        );
+        assert_eq!(
+            documents[0]
+                .collate
+                .as_ref()
+                .map(|collate| return PathBuf::from(&collate.backs)),
+            Some(root.join("pets/backs.pdf"))
+        );
+
+        // Printing stops when any file is gone, backs included
+        assert!(to_absolute(root, vec![document("pets/missing.pdf", None)]).is_err());
+        let gone_backs = document("pets/scan.pdf", Some("pets/gone.pdf"));
+        assert!(to_absolute(root, vec![gone_backs]).is_err());
+
+        return Ok(());
+    }
+}
@@ -1,60 +1,55 @@
+use base64::prelude::{Engine as _, BASE64_STANDARD};
 use core::fmt;
-use mupdf::pdf::PdfDocument;
-use printers::common::base::job::PrinterJobOptions;
-use std::fs::File;
-use std::hash::{DefaultHasher, Hash, Hasher};
-use std::io::{BufReader, BufWriter, Read, Write};
+use pdfprint_core::blank::{self, BlankOptions, BlankPages};
+use pdfprint_core::export::{self, ExportManifest, ExportOptions};
+use pdfprint_core::metadata::{self, MetadataOptions};
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf;
+use pdfprint_core::preflight::PreflightReport;
+use pdfprint_core::printing::{self, PrintOptions, Printer};
+use pdfprint_core::render;
+use pdfprint_core::repair::{self, RepairReport};
+use pdfprint_core::scan::{self, Entry};
+use pdfprint_core::scan_filter::{ScanFilter, ScanFilters};
+use pdfprint_core::select::PageSelector;
+use pdfprint_core::split::{self, SplitOptions, SplitPart};
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tauri_plugin_log::fern::FormatCallback;
 use tauri_plugin_log::TimezoneStrategy;
-use tempfile::NamedTempFile;
 use time::macros::format_description;
 
 use log::{error, info, warn, Record};
-use std::fs::{create_dir_all, read_dir};
-use std::time::Duration;
+use std::fs::create_dir_all;
 use tauri::path::PathResolver;
 use tauri::{Emitter, Listener, Manager};
 
+use crate::history::HistoryStatus;
 use crate::mutex_utils::LockResultExt;
-
-mod file_utils;
+use crate::print_queue::PrintQueue;
+use crate::scan_filters::{load_scan_filters, save_scan_filters, SCAN_FILTERS_FILE};
+use crate::search::SearchIndex;
+use crate::workspaces::{AppState, MIGRATIONS, STATE_VERSION};
+
+pub mod cli;
+mod entry_ids;
+mod history;
+mod hot_folder;
+mod jobs;
+mod loose_files;
 mod menu;
 mod mutex_utils;
-mod pdf;
-
-#[derive(Default, serde::Serialize, serde::Deserialize, Debug, Clone)]
-struct AppState {
-    workspace: Option<String>,
-}
-
-#[derive(serde::Serialize, Clone)]
-struct PdfDetails {
-    name: String,
-    path: String,
-    pages: i32,
-    size: u64,
-    parent: Option<u64>,
-    id: u64,
-}
-
-#[derive(serde::Serialize, Clone)]
-struct DirEntry {
-    name: String,
-    parent: Option<u64>,
-    path: String,
-    id: u64,
-}
-
-#[derive(serde::Serialize, Clone)]
-#[serde(tag = "type")]
-enum Entry {
-    #[serde(rename = "pdf")]
-    PdfDetails(PdfDetails),
-    #[serde(rename = "dir")]
-    DirEntry(DirEntry),
-}
+mod persistence;
+mod print_queue;
+mod print_service;
+mod scan_filters;
+mod search;
+mod settings;
+mod workspaces;
+
+const WORKSPACE_FILE: &str = "workspace.json";
+/// Resolution previews are rendered at when none is asked for
+const PREVIEW_DPI: f32 = 96.0;
 
 #[derive(serde::Serialize, Clone)]
 struct FolderProcessedPayload {
@@ -67,96 +62,69 @@ fn get_workspace_root(app_handle: &tauri::AppHandle) -> Result<String, String> {
         .state::<Mutex<AppState>>()
         .lock()
         .read_or_panic()
-        .workspace
+        .active
         .clone()
         .ok_or_else(|| return "No workspace set".to_string())?;
 
     return Ok(workspace);
 }
 
-fn calculate_hash<T: Hash>(t: &T) -> u64 {
-    let mut s = DefaultHasher::new();
-    t.hash(&mut s);
-    return s.finish();
+fn get_app_data_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    return PathResolver::app_data_dir(app_handle.path())
+        .map_err(|_| return "Failed to get app data directory".to_string());
 }
 
-fn process_folder(app_handle: &tauri::AppHandle, path: &Path) -> Result<(), String> {
-    let handle_clone = app_handle.clone();
-
-    let parent = calculate_hash(&path.to_string_lossy().to_string());
-    let entries: std::fs::ReadDir = read_dir(path).map_err(|e| return e.to_string())?;
-
-    info!("Processing folder: {}", path.to_string_lossy());
-
-    let pdfs: Vec<Entry> = entries
-        .filter(|entry| {
-            return entry.as_ref().is_ok_and(|entry| {
-                return entry.file_type().is_ok_and(|x| {
-                    return x.is_dir()
-                        && !["pages", "numbers", "key"].contains(
-                            &file_utils::get_extension_from_filename(
-                                &entry.file_name().to_string_lossy(),
-                            )
-                            .unwrap_or(""),
-                        );
-                }) || file_utils::get_extension_from_filename(
-                    &entry.file_name().to_string_lossy(),
-                )
-                .unwrap_or("")
-                    == "pdf";
-            });
-        })
-        .filter_map(|entry: Result<std::fs::DirEntry, std::io::Error>| {
-            return entry.map_or(None, |dir_entry| {
-                let path = dir_entry.path().to_string_lossy().to_string();
-                if dir_entry.file_type().is_ok_and(|f| return f.is_dir()) {
-                    let path_clone = path.clone();
-                    return Some(Entry::DirEntry(DirEntry {
-                        name: dir_entry.file_name().to_string_lossy().to_string(),
-                        parent: Some(parent),
-                        path,
-                        id: calculate_hash(&path_clone),
-                    }));
-                }
+fn get_scan_filter(app_handle: &tauri::AppHandle, path: &Path) -> ScanFilter {
+    let Ok(root) = get_workspace_root(app_handle) else {
+        return ScanFilter::new(path, ScanFilters::default());
+    };
 
-                let name: String = dir_entry.file_name().to_string_lossy().to_string();
-                let metadata: std::fs::Metadata = dir_entry.metadata().ok()?;
-                let size: u64 = metadata.len();
-                let document =
-                    PdfDocument::open(&dir_entry.path().as_path().to_string_lossy()).ok()?;
-
-                let pages = document.page_count().unwrap_or(0);
-
-                return Some(Entry::PdfDetails(PdfDetails {
-                    name,
-                    path: path.clone(),
-                    pages,
-                    size,
-                    parent: Some(parent),
-                    id: calculate_hash(&path),
-                }));
-            });
-        })
-        .collect();
+    let filters = get_app_data_dir(app_handle)
+        .and_then(|app_data| return load_scan_filters(&app_data.join(SCAN_FILTERS_FILE), &root))
+        .unwrap_or_else(|err| {
+            error!("{err}, scanning with the default filters");
+            return ScanFilters::default();
+        });
+
+    return ScanFilter::new(Path::new(&root), filters);
+}
 
-    info!("Found {} pdfs in folder", pdfs.len());
+fn process_folder(app_handle: &tauri::AppHandle, path: &Path) -> Result<(), String> {
+    let filter = get_scan_filter(app_handle, path);
+    let entries = entry_ids::with_entry_ids(app_handle, |ids| {
+        return scan::scan_folder(path, &filter, ids, &app_handle.state::<Passwords>());
+    })?;
 
-    handle_clone
+    app_handle
         .emit(
             "folder-processed",
             FolderProcessedPayload {
                 folder: path.to_string_lossy().to_string(),
-                entries: pdfs,
+                entries,
             },
         )
         .map_err(|e| return e.to_string())?;
 
+    // Picks up documents added or changed since the workspace was last indexed
+    app_handle.state::<Arc<SearchIndex>>().refresh(app_handle);
+
     return Ok(());
 }
 
 #[tauri::command]
 #[allow(clippy::needless_pass_by_value)]
 fn frontend_ready(app_handle: tauri::AppHandle) {
+    // Loading happens before the frontend listens for events, so report failures again now
+    let load_error = app_handle
+        .state::<Mutex<Option<StateLoadError>>>()
+        .lock()
+        .read_or_panic()
+        .take();
+    if let Some(load_error) = load_error {
+        let _ = app_handle.emit("state-load-failed", load_error);
+    }
+    loose_files::emit_loose_files(&app_handle);
+
     if let Ok(root) = get_workspace_root(&app_handle) {
         let workspace_path = Path::new(&root);
 
@@ -174,76 +142,276 @@ fn load_dir(app_handle: tauri::AppHandle, folder: String) {
     }
 }
 
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+fn get_scan_filters(app_handle: tauri::AppHandle) -> Result<ScanFilters, String> {
+    let root = get_workspace_root(&app_handle)?;
+    let app_data = get_app_data_dir(&app_handle)?;
+
+    return load_scan_filters(&app_data.join(SCAN_FILTERS_FILE), &root);
+}
+
 #[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+fn set_scan_filters(app_handle: tauri::AppHandle, filters: ScanFilters) -> Result<(), String> {
+    let root = get_workspace_root(&app_handle)?;
+    let app_data = get_app_data_dir(&app_handle)?;
+
+    save_scan_filters(&app_data.join(SCAN_FILTERS_FILE), &root, filters)?;
+    // Rescan the workspace so the new filters take effect
+    let _ = app_handle.emit("scan-filters-changed", &root);
+
+    return process_folder(&app_handle, Path::new(&root));
+}
+
+/// A combined document handed to a printer
+struct SentPrint {
+    printer: Printer,
+    job: u64,
+    /// Print history entry for the job, unset when it could not be recorded
+    history_id: Option<u64>,
+}
+
+/// Combines the documents and sends them to the named printer, or the default printer,
+/// recording the attempt in the print history
+fn print_pdfs(
+    app_handle: &tauri::AppHandle,
+    printer: Option<&str>,
+    pdfs: Vec<pdf::PdfPrintDetails>,
+    options: pdf::CombineOptions,
+    print_options: &PrintOptions,
+) -> Result<SentPrint, String> {
+    let documents = history::snapshot_documents(&pdfs);
+
+    let passwords = app_handle.state::<Passwords>();
+    let sent = pdf::create_combined_pdf(pdfs, options, &passwords).and_then(|combined_doc| {
+        let printer = printing::find_printer(printer)?;
+        let job = printing::print_document(&printer, &combined_doc, print_options)?;
+        let page_count = combined_doc.page_count().unwrap_or_default();
+
+        return Ok((printer, job, page_count));
+    });
+
+    return match sent {
+        Ok((printer, job, page_count)) => {
+            let history_id = history::record(
+                app_handle,
+                Some(printer.name.clone()),
+                documents,
+                options,
+                print_options,
+                page_count,
+                HistoryStatus::Sent,
+            );
+            Ok(SentPrint {
+                printer,
+                job,
+                history_id,
+            })
+        }
+        Err(error) => {
+            history::record(
+                app_handle,
+                printer.map(str::to_string),
+                documents,
+                options,
+                print_options,
+                0,
+                HistoryStatus::Failed {
+                    error: error.clone(),
+                },
+            );
+            Err(error)
+        }
+    };
+}
+
+/// Adds the documents to the print queue for the default printer, returning the queued job id
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
 fn print_to_default(
     app_handle: tauri::AppHandle,
     pdfs: Vec<pdf::PdfPrintDetails>,
+    options: Option<pdf::CombineOptions>,
+    print_options: Option<PrintOptions>,
+) -> Result<u64, String> {
+    return app_handle.state::<Arc<PrintQueue>>().enqueue(
+        None,
+        pdfs,
+        options.unwrap_or_default(),
+        print_options.unwrap_or_default(),
+    );
+}
+
+/// Combines the documents into `file` with the given metadata, password protecting it when
+/// `encryption` is given
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+fn save_to_file(
+    app_handle: tauri::AppHandle,
+    pdfs: Vec<pdf::PdfPrintDetails>,
+    file: &str,
+    options: Option<pdf::CombineOptions>,
+    encryption: Option<pdf::EncryptionOptions>,
+    metadata: Option<MetadataOptions>,
 ) -> Result<(), String> {
-    let combined_doc = pdf::create_combined_pdf(pdfs)?;
+    let mut combined_doc = pdf::create_combined_pdf(
+        pdfs,
+        options.unwrap_or_default(),
+        &app_handle.state::<Passwords>(),
+    )?;
 
-    // Create a temporary file to then send to a printer
-    let file = NamedTempFile::with_suffix(".pdf").map_err(|e| return e.to_string())?;
-    let real_file = file.as_file();
-    let mut writer = BufWriter::new(real_file);
-    let file_path = file.path().to_owned();
+    let path = Path::new(file);
+    let default_name = path.file_stem().map_or_else(
+        || return "Combined".to_string(),
+        |stem| return stem.to_string_lossy().to_string(),
+    );
+    metadata::write_metadata(
+        &mut combined_doc,
+        &metadata.unwrap_or_default(),
+        &default_name,
+    )?;
 
-    combined_doc
-        .write_to(&mut writer)
-        .map_err(|e| return e.to_string())?;
-    writer.flush().map_err(|e| return e.to_string())?;
-    drop(writer);
-
-    let def_printer = printers::get_default_printer()
-        .ok_or_else(|| return "Could not get default printer".to_string())?;
-
-    let job = def_printer.print_file(
-        &file_path.to_string_lossy(),
-        PrinterJobOptions {
-            name: Some("Pet Print PDF Job"),
-            raw_properties: &[],
-        },
+    return match encryption {
+        Some(encryption) => pdf::save_encrypted_document(&combined_doc, path, &encryption),
+        None => pdf::save_document(&combined_doc, path),
+    };
+}
+
+/// Checks the documents for anything likely to print badly, and how many sheets they need
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+fn preflight(
+    app_handle: tauri::AppHandle,
+    pdfs: Vec<pdf::PdfPrintDetails>,
+    options: Option<pdf::CombineOptions>,
+    print_options: Option<PrintOptions>,
+) -> PreflightReport {
+    return pdfprint_core::preflight::preflight(
+        pdfs,
+        options.unwrap_or_default(),
+        &print_options.unwrap_or_default(),
+        &app_handle.state::<Passwords>(),
+    );
+}
+
+/// Lists the blank pages of each document, so they can be reviewed before printing
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+fn find_blank_pages(
+    app_handle: tauri::AppHandle,
+    paths: Vec<String>,
+    options: Option<BlankOptions>,
+) -> Result<Vec<BlankPages>, String> {
+    let options = options.unwrap_or_default();
+    let passwords = app_handle.state::<Passwords>();
+
+    return paths
+        .into_iter()
+        .map(|path| -> Result<BlankPages, String> {
+            let pages = blank::find_blank_pages(Path::new(&path), options, &passwords)?;
+            return Ok(BlankPages { path, pages });
+        })
+        .collect();
+}
+
+/// Opens a password protected document for the rest of the session, returning its page count.
+/// The password is only kept in memory.
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+fn unlock_pdf(app_handle: tauri::AppHandle, path: String, password: String) -> Result<i32, String> {
+    let path = Path::new(&path);
+    let passwords = app_handle.state::<Passwords>();
+    passwords.unlock(path, &password)?;
+    let pages = pdf::count_pages(path, &passwords)?;
+
+    // Its text could not be indexed while it was locked
+    app_handle.state::<Arc<SearchIndex>>().refresh(&app_handle);
+
+    return Ok(pages);
+}
+
+/// Writes a clean copy of a damaged document to `out`, which may be the document itself
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+fn repair_pdf(app_handle: tauri::AppHandle, path: &str, out: &str) -> Result<RepairReport, String> {
+    let report = repair::repair_pdf(
+        Path::new(path),
+        Path::new(out),
+        &app_handle.state::<Passwords>(),
     )?;
-    file.keep().map_err(|e| return e.to_string())?;
-
-    tauri::async_runtime::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_millis(500));
-
-        for _i in 0..10 {
-            let active_jobs = def_printer.get_active_jobs();
-            let job_is_active = active_jobs
-                .iter()
-                .any(|active_job| return active_job.id == job);
-            if job_is_active {
-                interval.tick().await;
-            } else {
-                let _ = app_handle.emit("printing_completed", ());
-                break;
-            }
-        }
 
-        drop(interval);
-    });
+    // The damaged copy may have had pages that could not be read
+    app_handle.state::<Arc<SearchIndex>>().refresh(&app_handle);
 
-    return Ok(());
+    return Ok(report);
 }
 
+/// Renders a page of a document as it would print, redactions included, as a base64 PNG.
+/// `position` is the 0-based page after collating, as in print ranges and redaction areas.
 #[tauri::command(rename_all = "snake_case")]
-fn save_to_file(pdfs: Vec<pdf::PdfPrintDetails>, file: &str) -> Result<(), String> {
-    let combined_doc_result = pdf::create_combined_pdf(pdfs);
-    let Ok(combined_doc) = combined_doc_result else {
-        // Swallow error, just do not try to write
-        return Ok(());
-    };
-    let file_to_save = File::create(file).map_err(|e| return e.to_string())?;
-    let mut writer = BufWriter::new(file_to_save);
+#[allow(clippy::needless_pass_by_value)]
+fn render_preview(
+    app_handle: tauri::AppHandle,
+    pdf: pdf::PdfPrintDetails,
+    position: i32,
+    dpi: Option<f32>,
+    options: Option<pdf::CombineOptions>,
+) -> Result<String, String> {
+    let png = render::render_page_png(
+        pdf,
+        position,
+        dpi.unwrap_or(PREVIEW_DPI),
+        options.unwrap_or_default(),
+        &app_handle.state::<Passwords>(),
+    )?;
 
-    combined_doc
-        .write_to(&mut writer)
-        .map_err(|e| return e.to_string())?;
-    writer.flush().map_err(|e| return e.to_string())?;
-    drop(writer);
+    return Ok(BASE64_STANDARD.encode(png));
+}
 
-    return Ok(());
+/// Lists the 0-based pages of a document, after collating, whose text the selector matches
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+fn preview_page_selection(
+    app_handle: tauri::AppHandle,
+    pdf: pdf::PdfPrintDetails,
+    selector: PageSelector,
+) -> Result<Vec<i32>, String> {
+    return pdf::preview_selection(&pdf, &selector, &app_handle.state::<Passwords>());
+}
+
+/// Cuts a document into parts written to `destination`
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+fn split_pdf(
+    app_handle: tauri::AppHandle,
+    source: &str,
+    destination: &str,
+    options: SplitOptions,
+) -> Result<Vec<SplitPart>, String> {
+    return split::split_document(
+        Path::new(source),
+        Path::new(destination),
+        &options,
+        &app_handle.state::<Passwords>(),
+    );
+}
+
+/// Writes each document as its own PDF into a folder or a ZIP archive, with a manifest
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+fn export_pdfs(
+    app_handle: tauri::AppHandle,
+    pdfs: Vec<pdf::PdfPrintDetails>,
+    destination: &str,
+    options: Option<ExportOptions>,
+) -> Result<ExportManifest, String> {
+    return export::export_documents(
+        pdfs,
+        Path::new(destination),
+        &options.unwrap_or_default(),
+        &app_handle.state::<Passwords>(),
+    );
 }
 
 fn formatter(out: FormatCallback, message: &fmt::Arguments, record: &Record) {
@@ -267,67 +435,203 @@ fn formatter(out: FormatCallback, message: &fmt::Arguments, record: &Record) {
     ));
 }
 
-fn load_workspace(app_handle: &tauri::AppHandle, workspace_json: &PathBuf) {
-    if let Ok(file) = File::open(workspace_json) {
-        let mut reader = BufReader::new(file);
-        let mut string = String::new();
-        if let Ok(res) = reader.read_to_string(&mut string) {
-            if res > 0 {
-                if let Ok(deserialized) = serde_json::from_str::<AppState>(&string) {
-                    let handle_clone = app_handle.clone();
-                    let state = handle_clone.state::<Mutex<AppState>>();
-                    if let Ok(mut state) = state.lock() {
-                        *state = deserialized;
+/// Why `workspace.json` could not be loaded, kept until the frontend is ready to show it
+#[derive(serde::Serialize, Clone, Debug)]
+struct StateLoadError {
+    message: String,
+    backup: Option<String>,
+}
+
+fn load_workspace(app_handle: &tauri::AppHandle, workspace_json: &Path) {
+    match persistence::read_versioned::<AppState>(workspace_json, STATE_VERSION, &MIGRATIONS) {
+        Ok(Some(deserialized)) => {
+            let handle_clone = app_handle.clone();
+            let state = handle_clone.state::<Mutex<AppState>>();
+            if let Ok(mut state) = state.lock() {
+                *state = deserialized;
+            }
+            let _ = handle_clone.emit("state-loaded", ());
+        }
+        Ok(None) => {}
+        Err(err) => {
+            error!("Could not load {}: {err}", workspace_json.to_string_lossy());
+
+            // Keep a corrupt file around instead of overwriting it on the next save. Newer and
+            // unreadable files stay where they are, saving refuses to replace them.
+            let backup = if err.is_corrupt() {
+                match persistence::backup_corrupt(workspace_json) {
+                    Ok(backup) => {
+                        warn!("Moved unreadable state to {}", backup.to_string_lossy());
+                        Some(backup.to_string_lossy().to_string())
+                    }
+                    Err(backup_err) => {
+                        error!("Could not back up unreadable state: {backup_err}");
+                        None
                     }
-                    let _ = handle_clone.emit("state-loaded", ());
                 }
-            }
+            } else {
+                None
+            };
+
+            let load_error = StateLoadError {
+                message: err.to_string(),
+                backup,
+            };
+            *app_handle
+                .state::<Mutex<Option<StateLoadError>>>()
+                .lock()
+                .read_or_panic() = Some(load_error.clone());
+            let _ = app_handle.emit("state-load-failed", load_error);
         }
     }
 }
 
-fn folder_chosen(app_handle: &tauri::AppHandle, path: String, workspace_json: &PathBuf) {
-    let handle_clone = app_handle.clone();
-    let state = handle_clone.state::<Mutex<AppState>>();
-
-    let Ok(mut mut_state) = state.lock() else {
-        warn!("Could not acquire lock!");
-        return;
+/// Applies a change to the app state, persists it and rescans if the active workspace changed
+fn update_state<F>(app_handle: &tauri::AppHandle, update: F) -> Result<(), String>
+where
+    F: FnOnce(&mut AppState) -> Result<(), String>,
+{
+    let workspace_json = get_app_data_dir(app_handle)?.join(WORKSPACE_FILE);
+    let state = app_handle.state::<Mutex<AppState>>();
+
+    let (previous_active, clone) = {
+        let mut mut_state = state
+            .lock()
+            .map_err(|_| return "Could not acquire lock!".to_string())?;
+        let previous_active = mut_state.active.clone();
+        update(&mut *mut_state)?;
+        (previous_active, mut_state.clone())
     };
-    mut_state.workspace = Some(path);
-    let clone = mut_state.clone();
-
-    match File::create(workspace_json) {
-        Ok(file) => {
-            let mut writer = BufWriter::new(file);
-            if serde_json::to_writer(&mut writer, &clone).is_ok() && writer.flush().is_ok() {
-                info!("Emitting update");
-                let _ = handle_clone.emit("state-updated", ());
-            }
-        }
-        Err(err) => {
-            error!("{err}");
-        }
+
+    persistence::write_versioned(&workspace_json, STATE_VERSION, &clone)?;
+
+    if let Err(err) = menu::update_recent_menu(app_handle, &clone) {
+        warn!("Could not update recent menu: {err}");
+    }
+    let _ = app_handle.emit("workspaces-updated", &clone);
+
+    if clone.active != previous_active {
+        info!("Emitting update");
+        let _ = app_handle.emit("workspace-changed", &clone.active);
+        let _ = app_handle.emit("state-updated", ());
+    }
+
+    return Ok(());
+}
+
+fn folder_chosen(app_handle: &tauri::AppHandle, path: &str) {
+    let result = update_state(app_handle, |state| {
+        state.add(path, None);
+        state.activate(path);
+        return Ok(());
+    });
+
+    if let Err(err) = result {
+        error!("{err}");
     }
 }
 
+fn switch_to(app_handle: &tauri::AppHandle, path: &str) -> Result<(), String> {
+    return update_state(app_handle, |state| {
+        if state.activate(path) {
+            return Ok(());
+        }
+        return Err(format!("Unknown workspace: {path}"));
+    });
+}
+
 #[tauri::command(rename_all = "snake_case")]
 #[allow(clippy::needless_pass_by_value)]
 fn select_workspace(app_handle: tauri::AppHandle, file: String) -> Result<(), String> {
-    let app_data = PathResolver::app_data_dir(app_handle.path())
-        .map_err(|_| return "Failed to get app data directory".to_string())?;
-    let workspace_json = app_data.join("workspace.json");
-    folder_chosen(&app_handle, file, &workspace_json);
+    folder_chosen(&app_handle, &file);
 
     return Ok(());
 }
 
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+fn list_workspaces(app_handle: tauri::AppHandle) -> AppState {
+    return app_handle
+        .state::<Mutex<AppState>>()
+        .lock()
+        .read_or_panic()
+        .clone();
+}
+
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+fn add_workspace(
+    app_handle: tauri::AppHandle,
+    path: String,
+    name: Option<String>,
+) -> Result<(), String> {
+    if !Path::new(&path).is_dir() {
+        return Err(format!("Not a folder: {path}"));
+    }
+
+    return update_state(&app_handle, |state| {
+        state.add(&path, name);
+        return Ok(());
+    });
+}
+
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+fn remove_workspace(app_handle: tauri::AppHandle, path: String) -> Result<(), String> {
+    return update_state(&app_handle, |state| return state.remove(&path));
+}
+
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+fn rename_workspace(
+    app_handle: tauri::AppHandle,
+    path: String,
+    name: String,
+) -> Result<(), String> {
+    return update_state(&app_handle, |state| return state.rename(&path, name));
+}
+
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+fn switch_workspace(app_handle: tauri::AppHandle, path: String) -> Result<(), String> {
+    return switch_to(&app_handle, &path);
+}
+
+/// Files opened with the app from Finder arrive as an event rather than as arguments
+#[cfg(target_os = "macos")]
+#[allow(clippy::needless_pass_by_value)]
+fn handle_run_event(app_handle: &tauri::AppHandle, event: tauri::RunEvent) {
+    if let tauri::RunEvent::Opened { urls } = event {
+        let paths: Vec<PathBuf> = urls
+            .into_iter()
+            .filter_map(|url| return url.to_file_path().ok())
+            .collect();
+        loose_files::add_paths(app_handle, &paths);
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+#[allow(clippy::needless_pass_by_value)]
+fn handle_run_event(_app_handle: &tauri::AppHandle, _event: tauri::RunEvent) {}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 #[allow(clippy::missing_panics_doc)]
 pub fn run() {
     #[allow(clippy::expect_used)]
     #[allow(clippy::large_stack_frames)]
     tauri::Builder::default()
+        // Has to be the first plugin so a second launch exits before setting anything up
+        .plugin(tauri_plugin_single_instance::init(
+            |app_handle, args, cwd| {
+                let paths = loose_files::paths_from_args(args.into_iter().skip(1), Path::new(&cwd));
+                loose_files::add_paths(app_handle, &paths);
+
+                if let Some(window) = app_handle.get_webview_window("main") {
+                    let _ = window.unminimize();
+                    let _ = window.set_focus();
+                }
+            },
+        ))
         .plugin(
             tauri_plugin_log::Builder::new()
                 .max_file_size(50_000 /* bytes */)
@@ -336,7 +640,16 @@ pub fn run() {
                 .build(),
         )
         .manage(Mutex::new(AppState::default()))
+        .manage(Mutex::new(None::<StateLoadError>))
         .manage(menu::PendingUpdate::new(Mutex::new(None)))
+        .manage(Arc::new(print_service::PrintService::default()))
+        .manage(Arc::new(PrintQueue::default()))
+        .manage(Arc::new(SearchIndex::default()))
+        .manage(hot_folder::HotFolder::default())
+        .manage(Mutex::new(loose_files::LooseFiles::default()))
+        .manage(Passwords::default())
+        .manage(jobs::SavedJobsStore::default())
+        .manage(history::HistoryStore::default())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
@@ -344,7 +657,7 @@ pub fn run() {
         .setup(|app: &mut tauri::App| {
             let app_data = PathResolver::app_data_dir(app.path())
                 .map_err(|_| return "Failed to get app data directory".to_string())?;
-            let workspace_json = app_data.join("workspace.json");
+            let workspace_json = app_data.join(WORKSPACE_FILE);
 
             if !app_data.exists() {
                 info!("No app data directory found. Creating it.");
@@ -367,32 +680,133 @@ pub fn run() {
                 });
             }
 
+            app.manage(Mutex::new(entry_ids::load(&app_data)));
+
             // Load workspace state from file if it exists
             load_workspace(app.handle(), &workspace_json);
 
+            if let Err(err) = app.state::<Arc<PrintQueue>>().start(app.handle()) {
+                error!("Could not start the print queue: {err}");
+            }
+
+            let settings = settings::load_settings(&app_data);
+            if let Err(err) = settings::apply_settings(app.handle(), &settings) {
+                error!("{err}");
+            }
+            app.manage(Mutex::new(settings));
+
             let _ = menu::setup_menu(app.handle())
                 .map_err(|_| return "Failed to setup menu".to_string());
+            {
+                let state = app
+                    .handle()
+                    .state::<Mutex<AppState>>()
+                    .lock()
+                    .read_or_panic()
+                    .clone();
+                if let Err(err) = menu::update_recent_menu(app.handle(), &state) {
+                    warn!("Could not update recent menu: {err}");
+                }
+            }
 
             {
                 let handle_clone = app.handle().clone();
                 app.handle().listen("folder-chosen", move |event| {
                     if let Ok(path) = serde_json::from_str::<String>(event.payload()) {
-                        folder_chosen(&handle_clone, path, &workspace_json);
+                        folder_chosen(&handle_clone, &path);
+                    }
+                });
+            }
+
+            {
+                let handle_clone = app.handle().clone();
+                app.handle().listen("open-recent", move |event| {
+                    if let Ok(path) = serde_json::from_str::<String>(event.payload()) {
+                        if let Err(err) = switch_to(&handle_clone, &path) {
+                            error!("{err}");
+                        }
                     }
                 });
             }
 
+            {
+                let handle_clone = app.handle().clone();
+                app.handle().listen("clear-recent", move |_event| {
+                    let result = update_state(&handle_clone, |state| {
+                        state.clear_recent();
+                        return Ok(());
+                    });
+                    if let Err(err) = result {
+                        error!("{err}");
+                    }
+                });
+            }
+
+            {
+                let cwd = std::env::current_dir().unwrap_or_default();
+                let paths = loose_files::paths_from_args(
+                    std::env::args_os()
+                        .skip(1)
+                        .map(|arg| return arg.to_string_lossy().to_string()),
+                    &cwd,
+                );
+                loose_files::add_paths(app.handle(), &paths);
+            }
+
             info!("All set up!");
 
             return Ok(());
         })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                loose_files::add_paths(window.app_handle(), paths);
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             frontend_ready,
             print_to_default,
             save_to_file,
+            export_pdfs,
+            split_pdf,
+            find_blank_pages,
+            preflight,
+            unlock_pdf,
+            repair_pdf,
+            preview_page_selection,
+            render_preview,
             select_workspace,
             load_dir,
+            get_scan_filters,
+            set_scan_filters,
+            list_workspaces,
+            add_workspace,
+            remove_workspace,
+            rename_workspace,
+            switch_workspace,
+            search::search_workspace,
+            jobs::list_saved_jobs,
+            jobs::create_saved_job,
+            jobs::update_saved_job,
+            jobs::delete_saved_job,
+            jobs::run_saved_job,
+            settings::get_settings,
+            settings::update_settings,
+            print_queue::list_print_queue,
+            print_queue::pause_print_queue,
+            print_queue::resume_print_queue,
+            print_queue::hold_print_job,
+            print_queue::release_print_job,
+            print_queue::move_print_job,
+            print_queue::cancel_print_job,
+            print_queue::retry_print_job,
+            print_queue::clear_finished_print_jobs,
+            history::list_print_history,
+            history::reprint_history_entry,
+            loose_files::add_loose_files,
+            loose_files::remove_loose_file,
+            loose_files::clear_loose_files,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(handle_run_event);
 }
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use log::{info, warn};
+use pdfprint_core::entry_id::RESERVED_ID;
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf;
+use pdfprint_core::scan::{self, DirEntry, Entry, PdfDetails};
+use tauri::{Emitter, Manager};
+
+use crate::entry_ids;
+use crate::mutex_utils::LockResultExt;
+
+/// Id of the pseudo folder loose files are listed under, never given to a real entry
+pub const LOOSE_FILES_ID: u64 = RESERVED_ID;
+/// Loose files get ids of their own, a file also in the workspace is listed in both places
+const LOOSE_FILES_NAMESPACE: &str = "loose";
+
+/// Files dropped on the window or opened with the app, listed alongside the workspace
+#[derive(Default)]
+pub struct LooseFiles {
+    files: Vec<PdfDetails>,
+}
+
+impl LooseFiles {
+    /// The pseudo folder followed by its files, or nothing when there are no loose files
+    pub fn entries(&self) -> Vec<Entry> {
+        if self.files.is_empty() {
+            return vec![];
+        }
+
+        return std::iter::once(Entry::DirEntry(DirEntry {
+            name: "Loose Files".to_string(),
+            parent: None,
+            path: String::new(),
+            id: LOOSE_FILES_ID,
+        }))
+        .chain(self.files.iter().cloned().map(Entry::PdfDetails))
+        .collect();
+    }
+}
+
+/// Resolves arguments given on the command line or forwarded from a second launch to the
+/// documents among them, skipping flags and anything that is not a readable document
+pub fn paths_from_args<I: IntoIterator<Item = String>>(args: I, cwd: &Path) -> Vec<PathBuf> {
+    return args
+        .into_iter()
+        .filter(|arg| return !arg.starts_with('-'))
+        .map(|arg| return cwd.join(arg))
+        .filter(|path| return path.is_file() && pdf::is_document_path(path))
+        .collect();
+}
+
+pub fn emit_loose_files(app_handle: &tauri::AppHandle) {
+    let entries = app_handle
+        .state::<Mutex<LooseFiles>>()
+        .lock()
+        .read_or_panic()
+        .entries();
+
+    let _ = app_handle.emit("loose-files-updated", entries);
+}
+
+/// Adds the documents among `paths` to the loose files, skipping ones already listed
+pub fn add_paths(app_handle: &tauri::AppHandle, paths: &[PathBuf]) {
+    let mut added = vec![];
+    for path in paths {
+        if !pdf::is_document_path(path) {
+            warn!("Skipping {}, it is not a document", path.to_string_lossy());
+            continue;
+        }
+
+        let metadata = match std::fs::metadata(path) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            Ok(_) => {
+                warn!("Skipping {}, it is not a file", path.to_string_lossy());
+                continue;
+            }
+            Err(err) => {
+                warn!("Skipping {}: {err}", path.to_string_lossy());
+                continue;
+            }
+        };
+
+        let details = entry_ids::with_entry_ids(app_handle, |ids| {
+            return ids.in_namespace(LOOSE_FILES_NAMESPACE, |ids| {
+                return scan::document_details(
+                    path,
+                    &metadata,
+                    Some(LOOSE_FILES_ID),
+                    ids,
+                    &app_handle.state::<Passwords>(),
+                );
+            });
+        });
+        match details {
+            Ok(details) => added.push(details),
+            Err(err) => warn!("Skipping {}: {err}", path.to_string_lossy()),
+        }
+    }
+
+    if added.is_empty() {
+        return;
+    }
+
+    {
+        let state = app_handle.state::<Mutex<LooseFiles>>();
+        let mut loose_files = state.lock().read_or_panic();
+        for details in added {
+            if loose_files
+                .files
+                .iter()
+                .all(|file| return file.id != details.id)
+            {
+                info!("Added loose file {}", details.path);
+                loose_files.files.push(details);
+            }
+        }
+    }
+
+    emit_loose_files(app_handle);
+}
+
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+pub fn add_loose_files(app_handle: tauri::AppHandle, paths: Vec<String>) {
+    let paths: Vec<PathBuf> = paths.into_iter().map(PathBuf::from).collect();
+
+    add_paths(&app_handle, &paths);
+}
+
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+pub fn remove_loose_file(app_handle: tauri::AppHandle, id: u64) {
+    app_handle
+        .state::<Mutex<LooseFiles>>()
+        .lock()
+        .read_or_panic()
+        .files
+        .retain(|file| return file.id != id);
+
+    emit_loose_files(&app_handle);
+}
+
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+pub fn clear_loose_files(app_handle: tauri::AppHandle) {
+    app_handle
+        .state::<Mutex<LooseFiles>>()
+        .lock()
+        .read_or_panic()
+        .files
+        .clear();
+
+    emit_loose_files(&app_handle);
+}
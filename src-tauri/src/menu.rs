@@ -2,7 +2,7 @@ use std::sync::Mutex;
 
 use log::{error, info, warn};
 use serde::Serialize;
-use tauri::menu::{MenuBuilder, MenuItem, MenuItemBuilder, SubmenuBuilder};
+use tauri::menu::{MenuBuilder, MenuItem, MenuItemBuilder, PredefinedMenuItem, SubmenuBuilder};
 use tauri::Emitter;
 use tauri::Manager;
 use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
@@ -10,6 +10,10 @@ use tauri_plugin_opener::OpenerExt;
 use tauri_plugin_updater::{Update, UpdaterExt};
 
 use crate::mutex_utils::LockResultExt;
+use crate::workspaces::AppState;
+
+/// Menu ids of the "Open Recent" entries are this prefix followed by the workspace path
+const RECENT_PREFIX: &str = "open_recent:";
 
 fn open_folder_handler(app: &tauri::AppHandle) {
     let handle_clone: tauri::AppHandle = app.app_handle().clone();
@@ -79,6 +83,56 @@ pub fn add_menu_item_to_submenu_at<R: tauri::Runtime>(
     return Ok(());
 }
 
+/// Rebuilds the "Open Recent" submenu from the recent workspace list
+pub fn update_recent_menu(app: &tauri::AppHandle, state: &AppState) -> Result<(), tauri::Error> {
+    let Some(root) = app.menu() else {
+        return Ok(());
+    };
+
+    let Some(file_menu) = root.get("file") else {
+        return Ok(());
+    };
+    let Some(recent_menu) = file_menu
+        .as_submenu()
+        .and_then(|file_submenu| return file_submenu.get("open_recent"))
+    else {
+        return Ok(());
+    };
+    let Some(recent_submenu) = recent_menu.as_submenu() else {
+        return Ok(());
+    };
+
+    for item in recent_submenu.items()? {
+        recent_submenu.remove(&item)?;
+    }
+
+    let recent = state.recent_workspaces();
+    if recent.is_empty() {
+        let no_recent = MenuItemBuilder::new("No Recent Workspaces")
+            .id("no_recent")
+            .enabled(false)
+            .build(app)?;
+        recent_submenu.append(&no_recent)?;
+
+        return Ok(());
+    }
+
+    for workspace in recent {
+        let item = MenuItemBuilder::new(&workspace.name)
+            .id(format!("{RECENT_PREFIX}{}", workspace.path))
+            .build(app)?;
+        recent_submenu.append(&item)?;
+    }
+
+    let clear_recent = MenuItemBuilder::new("Clear Recent")
+        .id("clear_recent")
+        .build(app)?;
+    recent_submenu.append(&PredefinedMenuItem::separator(app)?)?;
+    recent_submenu.append(&clear_recent)?;
+
+    return Ok(());
+}
+
 async fn check_for_update(
     app: &tauri::AppHandle,
 ) -> Result<Option<UpdateMetadata>, tauri_plugin_updater::Error> {
@@ -143,8 +197,14 @@ pub fn setup_menu(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Erro
         .accelerator("CmdOrCtrl+O")
         .build(app)?;
 
+    let open_recent = SubmenuBuilder::new(app, "Open Recent")
+        .id("open_recent")
+        .build()?;
+
     let file_submenu = SubmenuBuilder::new(app, "File")
+        .id("file")
         .item(&open_folder)
+        .item(&open_recent)
         .separator()
         .services()
         .separator()
@@ -184,6 +244,12 @@ pub fn setup_menu(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Erro
             "open_folder" => {
                 open_folder_handler(app);
             }
+            "clear_recent" => {
+                let _ = app.emit("clear-recent", ());
+            }
+            id if id.starts_with(RECENT_PREFIX) => {
+                let _ = app.emit("open-recent", id.trim_start_matches(RECENT_PREFIX));
+            }
             "view_logs" => {
                 info!("Opening logs");
                 if let Ok(path) = app.app_handle().path().app_log_dir() {
@@ -0,0 +1,310 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use tempfile::NamedTempFile;
+
+/// Upgrades a document from the version matching its index in the migration list to the next one
+pub type Migration = fn(Value) -> Result<Value, String>;
+
+const VERSION_KEY: &str = "version";
+
+/// Why a versioned file could not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadError {
+    /// Written by a newer version of the app, left as it is rather than replaced
+    Newer { found: u32, supported: u32 },
+    /// The file is there but could not be read, such as when permission is denied
+    Io(String),
+    /// Not JSON, or not in the expected format
+    Corrupt(String),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Self::Newer { found, supported } => write!(
+                f,
+                "Written by a newer version of the app (version {found}, supported {supported})"
+            ),
+            Self::Io(message) | Self::Corrupt(message) => write!(f, "{message}"),
+        };
+    }
+}
+
+impl ReadError {
+    /// Whether the file is unreadable whatever version of the app reads it, so it should be
+    /// backed up with [`backup_corrupt`] before starting over
+    pub const fn is_corrupt(&self) -> bool {
+        return matches!(self, Self::Corrupt(_));
+    }
+}
+
+/// Reads the file, `Ok(None)` when there is none
+fn read_contents(path: &Path) -> Result<Option<String>, ReadError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(ReadError::Io(err.to_string())),
+    };
+
+    let mut contents = String::new();
+    BufReader::new(file)
+        .read_to_string(&mut contents)
+        .map_err(|e| return ReadError::Io(e.to_string()))?;
+
+    return Ok(Some(contents));
+}
+
+fn version_of(json: &Value) -> Result<u32, ReadError> {
+    return json
+        .get(VERSION_KEY)
+        .map_or(Some(0), Value::as_u64)
+        .and_then(|found| return u32::try_from(found).ok())
+        .ok_or_else(|| return ReadError::Corrupt("Invalid version field".to_string()));
+}
+
+/// Writes JSON to a temporary file next to `path` and renames it into place,
+/// so a crash mid-write never leaves a truncated file behind.
+pub fn write_json_atomic<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| return format!("No parent directory for {}", path.to_string_lossy()))?;
+    let mut temp_file = NamedTempFile::new_in(dir).map_err(|e| return e.to_string())?;
+
+    {
+        let mut writer = BufWriter::new(temp_file.as_file_mut());
+        serde_json::to_writer(&mut writer, value).map_err(|e| return e.to_string())?;
+        writer.flush().map_err(|e| return e.to_string())?;
+    }
+    temp_file
+        .as_file()
+        .sync_all()
+        .map_err(|e| return e.to_string())?;
+    temp_file
+        .persist(path)
+        .map_err(|e| return e.error.to_string())?;
+
+    return Ok(());
+}
+
+/// Writes `value` with a `version` field added next to its own fields. A file that could not
+/// be read, or that was written by a newer version of the app, is never replaced.
+pub fn write_versioned<T: Serialize>(path: &Path, version: u32, value: &T) -> Result<(), String> {
+    let existing = read_contents(path)
+        .map_err(|err| return format!("Not replacing {}: {err}", path.to_string_lossy()))?;
+    let newer = existing
+        .and_then(|contents| return serde_json::from_str::<Value>(&contents).ok())
+        .and_then(|json| return version_of(&json).ok())
+        .filter(|found| return *found > version);
+    if let Some(found) = newer {
+        return Err(format!(
+            "Not replacing {}: {}",
+            path.to_string_lossy(),
+            ReadError::Newer {
+                found,
+                supported: version
+            }
+        ));
+    }
+
+    let mut json = serde_json::to_value(value).map_err(|e| return e.to_string())?;
+    let object = json
+        .as_object_mut()
+        .ok_or_else(|| return "Only objects can be versioned".to_string())?;
+    object.insert(VERSION_KEY.to_string(), Value::from(version));
+
+    return write_json_atomic(path, &json);
+}
+
+/// Reads a file written by [`write_versioned`], running the migrations needed to bring
+/// it up to `version`. Files without a version are treated as version 0.
+///
+/// Returns `Ok(None)` when the file does not exist or is empty.
+pub fn read_versioned<T: DeserializeOwned>(
+    path: &Path,
+    version: u32,
+    migrations: &[Migration],
+) -> Result<Option<T>, ReadError> {
+    let Some(contents) = read_contents(path)? else {
+        return Ok(None);
+    };
+    if contents.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let mut json: Value =
+        serde_json::from_str(&contents).map_err(|e| return ReadError::Corrupt(e.to_string()))?;
+    let found = version_of(&json)?;
+
+    if found > version {
+        return Err(ReadError::Newer {
+            found,
+            supported: version,
+        });
+    }
+
+    for migration in migrations
+        .iter()
+        .skip(found as usize)
+        .take((version - found) as usize)
+    {
+        json = migration(json).map_err(ReadError::Corrupt)?;
+    }
+
+    if let Some(object) = json.as_object_mut() {
+        object.remove(VERSION_KEY);
+    }
+
+    return serde_json::from_value(json)
+        .map(Some)
+        .map_err(|e| return ReadError::Corrupt(e.to_string()));
+}
+
+/// Moves an unreadable file out of the way so it is not overwritten by the next save
+pub fn backup_corrupt(path: &Path) -> Result<PathBuf, String> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| return duration.as_secs());
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| return format!("No file name for {}", path.to_string_lossy()))?
+        .to_string_lossy();
+    let backup = path.with_file_name(format!("{file_name}.corrupt-{timestamp}"));
+
+    std::fs::rename(path, &backup).map_err(|e| return e.to_string())?;
+
+    return Ok(backup);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+    struct Stored {
+        name: String,
+    }
+
+    fn stored(name: &str) -> Stored {
+        return Stored {
+            name: name.to_string(),
+        };
+    }
+
+    #[test]
+    fn writes_json_atomically() -> Result<(), String> {
+        let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+        let path = dir.path().join("stored.json");
+        std::fs::write(&path, "previous").map_err(|e| return e.to_string())?;
+
+        write_json_atomic(&path, &stored("first"))?;
+
+        let contents = std::fs::read_to_string(&path).map_err(|e| return e.to_string())?;
+        assert_eq!(contents, r#"{"name":"first"}"#);
+        // The temporary file was renamed into place rather than left next to it
+        let files = std::fs::read_dir(dir.path())
+            .map_err(|e| return e.to_string())?
+            .count();
+        assert_eq!(files, 1);
+
+        return Ok(());
+    }
+
+    #[test]
+    fn refuses_files_without_a_parent() {
+        assert!(write_json_atomic(Path::new(""), &stored("first")).is_err());
+    }
+
+    #[test]
+    fn reads_missing_and_empty_files_as_none() -> Result<(), String> {
+        let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+        let path = dir.path().join("stored.json");
+        assert_eq!(read_versioned::<Stored>(&path, 1, &[]), Ok(None));
+
+        std::fs::write(&path, "  \n").map_err(|e| return e.to_string())?;
+        assert_eq!(read_versioned::<Stored>(&path, 1, &[]), Ok(None));
+
+        return Ok(());
+    }
+
+    #[test]
+    fn reads_back_versioned_files() -> Result<(), String> {
+        let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+        let path = dir.path().join("stored.json");
+
+        write_versioned(&path, 1, &stored("first"))?;
+        assert_eq!(read_versioned(&path, 1, &[]), Ok(Some(stored("first"))));
+
+        return Ok(());
+    }
+
+    #[test]
+    fn migrates_older_files() -> Result<(), String> {
+        fn rename_title(mut json: Value) -> Result<Value, String> {
+            let object = json
+                .as_object_mut()
+                .ok_or_else(|| return "Not an object".to_string())?;
+            let title = object.remove("title").unwrap_or_default();
+            object.insert("name".to_string(), title);
+            return Ok(json);
+        }
+
+        let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+        let path = dir.path().join("stored.json");
+        std::fs::write(&path, r#"{"title":"legacy"}"#).map_err(|e| return e.to_string())?;
+
+        assert_eq!(
+            read_versioned(&path, 1, &[rename_title]),
+            Ok(Some(stored("legacy")))
+        );
+
+        return Ok(());
+    }
+
+    #[test]
+    fn leaves_files_from_newer_versions_alone() -> Result<(), String> {
+        let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+        let path = dir.path().join("stored.json");
+        write_versioned(&path, 2, &stored("newer"))?;
+
+        let read = read_versioned::<Stored>(&path, 1, &[]);
+        assert_eq!(
+            read,
+            Err(ReadError::Newer {
+                found: 2,
+                supported: 1
+            })
+        );
+        assert!(!read.is_err_and(|err| return err.is_corrupt()));
+
+        assert!(write_versioned(&path, 1, &stored("older")).is_err());
+        assert_eq!(read_versioned(&path, 2, &[]), Ok(Some(stored("newer"))));
+
+        return Ok(());
+    }
+
+    #[test]
+    fn reports_corrupt_files() -> Result<(), String> {
+        let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+        let path = dir.path().join("stored.json");
+        std::fs::write(&path, "{not json").map_err(|e| return e.to_string())?;
+
+        assert!(read_versioned::<Stored>(&path, 1, &[]).is_err_and(|err| return err.is_corrupt()));
+
+        let backup = backup_corrupt(&path)?;
+        assert_eq!(
+            std::fs::read_to_string(backup).map_err(|e| return e.to_string())?,
+            "{not json"
+        );
+        write_versioned(&path, 1, &stored("fresh"))?;
+        assert_eq!(read_versioned(&path, 1, &[]), Ok(Some(stored("fresh"))));
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,760 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use log::{error, info, warn};
+use pdfprint_core::pdf::{CombineOptions, PdfPrintDetails};
+use pdfprint_core::printing::{self, PrintOptions};
+use tauri::{Emitter, Manager};
+
+use crate::history::{self, HistoryStatus};
+use crate::mutex_utils::LockResultExt;
+use crate::persistence;
+use crate::{get_app_data_dir, print_pdfs, SentPrint};
+
+const PRINT_QUEUE_FILE: &str = "print_queue.json";
+const PRINT_QUEUE_VERSION: u32 = 1;
+/// Completed, cancelled and timed out jobs kept in the queue until cleared
+const MAX_FINISHED_JOBS: usize = 100;
+/// How often a printer's spooler is checked for the job that was sent to it
+const JOB_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How long a printer may take to finish a job before the next one is sent anyway
+const PRINT_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum QueuedJobStatus {
+    Queued,
+    /// Kept in the queue but skipped until released
+    Held,
+    Printing,
+    Completed,
+    Failed {
+        error: String,
+    },
+    Cancelled,
+    /// Sent, but the printer did not finish it within [`PRINT_TIMEOUT`], it may still print
+    TimedOut,
+}
+
+impl QueuedJobStatus {
+    const fn is_finished(&self) -> bool {
+        return matches!(self, Self::Completed | Self::Cancelled | Self::TimedOut);
+    }
+
+    /// Whether nothing more will happen to the job unless it is retried
+    pub const fn is_settled(&self) -> bool {
+        return self.is_finished() || matches!(self, Self::Failed { .. });
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct QueuedJob {
+    pub id: u64,
+    pub name: String,
+    /// Printer the job was submitted to, the default printer is resolved when queueing
+    pub printer: String,
+    pub documents: Vec<PdfPrintDetails>,
+    pub combine_options: CombineOptions,
+    pub print_options: PrintOptions,
+    /// Seconds since the Unix epoch
+    pub submitted_at: u64,
+    #[serde(flatten)]
+    pub status: QueuedJobStatus,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct QueueState {
+    /// Stops new jobs from being sent to any printer
+    pub paused: bool,
+    /// Jobs in the order they are sent, per printer
+    pub jobs: Vec<QueuedJob>,
+    next_id: u64,
+}
+
+impl QueueState {
+    fn job_mut(&mut self, id: u64) -> Result<&mut QueuedJob, String> {
+        return self
+            .jobs
+            .iter_mut()
+            .find(|job| return job.id == id)
+            .ok_or_else(|| return format!("Unknown print job: {id}"));
+    }
+
+    fn status(&self, id: u64) -> Option<&QueuedJobStatus> {
+        return self
+            .jobs
+            .iter()
+            .find(|job| return job.id == id)
+            .map(|job| return &job.status);
+    }
+
+    fn push(
+        &mut self,
+        name: String,
+        printer: String,
+        documents: Vec<PdfPrintDetails>,
+        combine_options: CombineOptions,
+        print_options: PrintOptions,
+    ) -> u64 {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.jobs.push(QueuedJob {
+            id,
+            name,
+            printer,
+            documents,
+            combine_options,
+            print_options,
+            submitted_at: now(),
+            status: QueuedJobStatus::Queued,
+        });
+        return id;
+    }
+
+    /// Marks the first queued job for `printer` as printing, unless the queue is paused
+    fn take_next(&mut self, printer: &str) -> Option<QueuedJob> {
+        if self.paused {
+            return None;
+        }
+
+        let job = self.jobs.iter_mut().find(|job| {
+            return job.printer == printer && job.status == QueuedJobStatus::Queued;
+        })?;
+        job.status = QueuedJobStatus::Printing;
+        return Some(job.clone());
+    }
+
+    /// Records how a printing job ended, unless it was cancelled or removed meanwhile
+    fn finish(&mut self, id: u64, status: QueuedJobStatus) {
+        if let Ok(job) = self.job_mut(id) {
+            if job.status == QueuedJobStatus::Printing {
+                job.status = status;
+            }
+        }
+    }
+
+    fn hold(&mut self, id: u64) -> Result<(), String> {
+        let job = self.job_mut(id)?;
+        if job.status != QueuedJobStatus::Queued {
+            return Err("Only queued jobs can be held".to_string());
+        }
+        job.status = QueuedJobStatus::Held;
+        return Ok(());
+    }
+
+    fn release(&mut self, id: u64) -> Result<(), String> {
+        let job = self.job_mut(id)?;
+        if job.status != QueuedJobStatus::Held {
+            return Err("Only held jobs can be released".to_string());
+        }
+        job.status = QueuedJobStatus::Queued;
+        return Ok(());
+    }
+
+    fn move_job(&mut self, id: u64, index: usize) -> Result<(), String> {
+        let position = self
+            .jobs
+            .iter()
+            .position(|job| return job.id == id)
+            .ok_or_else(|| return format!("Unknown print job: {id}"))?;
+        let job = self.jobs.remove(position);
+        let index = index.min(self.jobs.len());
+        self.jobs.insert(index, job);
+        return Ok(());
+    }
+
+    /// Cancels a job, a printing job is also removed from the printer's spooler by its worker
+    fn cancel(&mut self, id: u64) -> Result<(), String> {
+        let job = self.job_mut(id)?;
+        if job.status.is_finished() {
+            return Err("The job has already finished".to_string());
+        }
+        job.status = QueuedJobStatus::Cancelled;
+        return Ok(());
+    }
+
+    fn retry(&mut self, id: u64) -> Result<(), String> {
+        let job = self.job_mut(id)?;
+        if !matches!(
+            job.status,
+            QueuedJobStatus::Failed { .. } | QueuedJobStatus::Cancelled | QueuedJobStatus::TimedOut
+        ) {
+            return Err("Only failed, cancelled or timed out jobs can be retried".to_string());
+        }
+        job.status = QueuedJobStatus::Queued;
+        return Ok(());
+    }
+
+    fn clear_settled(&mut self) {
+        self.jobs.retain(|job| return !job.status.is_settled());
+    }
+
+    fn prune_finished(&mut self) {
+        let finished = self
+            .jobs
+            .iter()
+            .filter(|job| return job.status.is_finished())
+            .count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED_JOBS);
+        self.jobs.retain(|job| {
+            if excess > 0 && job.status.is_finished() {
+                excess -= 1;
+                return false;
+            }
+            return true;
+        });
+    }
+}
+
+/// Jobs waiting to be printed, sent one at a time to each printer by a worker thread per printer
+#[derive(Default)]
+pub struct PrintQueue {
+    state: Mutex<QueueState>,
+    wake: Condvar,
+    workers: Mutex<HashSet<String>>,
+    app_handle: OnceLock<tauri::AppHandle>,
+    queue_json: OnceLock<PathBuf>,
+}
+
+fn now() -> u64 {
+    return SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| return duration.as_secs());
+}
+
+fn load(queue_json: &Path) -> QueueState {
+    return match persistence::read_versioned(queue_json, PRINT_QUEUE_VERSION, &[]) {
+        Ok(state) => state.unwrap_or_default(),
+        Err(err) => {
+            error!("Could not load the print queue: {err}");
+            if err.is_corrupt() {
+                if let Ok(backup) = persistence::backup_corrupt(queue_json) {
+                    info!(
+                        "Moved unreadable print queue to {}",
+                        backup.to_string_lossy()
+                    );
+                }
+            }
+            QueueState::default()
+        }
+    };
+}
+
+/// Sends the job to its printer, recording it in the history
+fn send(app_handle: &tauri::AppHandle, job: &QueuedJob) -> Result<SentPrint, String> {
+    let print_options = PrintOptions {
+        job_name: job
+            .print_options
+            .job_name
+            .clone()
+            .or_else(|| return Some(job.name.clone())),
+        ..job.print_options.clone()
+    };
+    return print_pdfs(
+        app_handle,
+        Some(&job.printer),
+        job.documents.clone(),
+        job.combine_options,
+        &print_options,
+    );
+}
+
+/// Names a job after its first document when no job name is given
+fn job_name(documents: &[PdfPrintDetails], print_options: &PrintOptions) -> String {
+    if let Some(name) = &print_options.job_name {
+        return name.clone();
+    }
+
+    return match documents {
+        [] => "Empty job".to_string(),
+        [document] => document.name.clone(),
+        [document, rest @ ..] => format!("{} and {} more", document.name, rest.len()),
+    };
+}
+
+impl PrintQueue {
+    /// Restores the queue saved by a previous run and starts sending its jobs
+    pub fn start(self: &Arc<Self>, app_handle: &tauri::AppHandle) -> Result<(), String> {
+        let queue_json = get_app_data_dir(app_handle)?.join(PRINT_QUEUE_FILE);
+        let mut state = load(&queue_json);
+
+        // The app closed while these were being sent, they may or may not have printed
+        for job in &mut state.jobs {
+            if job.status == QueuedJobStatus::Printing {
+                job.status = QueuedJobStatus::Failed {
+                    error: "Interrupted when the app closed".to_string(),
+                };
+            }
+        }
+
+        let _ = self.queue_json.set(queue_json);
+        let _ = self.app_handle.set(app_handle.clone());
+        {
+            let mut current = self.state.lock().read_or_panic();
+            *current = state;
+            // Jobs restored from disk are still sent when the queue can not be saved
+            if let Err(err) = self.save(&current) {
+                error!("Could not save the print queue: {err}");
+            }
+        }
+        self.wake_workers();
+
+        return Ok(());
+    }
+
+    pub fn snapshot(&self) -> QueueState {
+        return self.state.lock().read_or_panic().clone();
+    }
+
+    /// Saves the queue and lets the frontend know it changed
+    fn save(&self, state: &QueueState) -> Result<(), String> {
+        let Some(queue_json) = self.queue_json.get() else {
+            return Err("The print queue has not been started".to_string());
+        };
+
+        persistence::write_versioned(queue_json, PRINT_QUEUE_VERSION, state)?;
+        if let Some(app_handle) = self.app_handle.get() {
+            let _ = app_handle.emit("print-queue-updated", state);
+        }
+
+        return Ok(());
+    }
+
+    /// Applies a change, saves it and wakes up the workers. A change that can not be saved is
+    /// undone, so a job reported as not queued is never printed later on.
+    fn modify<T, F>(self: &Arc<Self>, update: F) -> Result<T, String>
+    where
+        F: FnOnce(&mut QueueState) -> Result<T, String>,
+    {
+        let result = {
+            let mut state = self.state.lock().read_or_panic();
+            let previous = state.clone();
+            let result = update(&mut state)?;
+            state.prune_finished();
+
+            if let Err(err) = self.save(&state) {
+                *state = previous;
+                return Err(err);
+            }
+            result
+        };
+        self.wake_workers();
+
+        return Ok(result);
+    }
+
+    /// Starts a worker for each printer with queued jobs and wakes them all up
+    fn wake_workers(self: &Arc<Self>) {
+        let printers: HashSet<String> = self
+            .state
+            .lock()
+            .read_or_panic()
+            .jobs
+            .iter()
+            .filter(|job| return job.status == QueuedJobStatus::Queued)
+            .map(|job| return job.printer.clone())
+            .collect();
+        for printer in printers {
+            self.ensure_worker(printer);
+        }
+        self.wake.notify_all();
+    }
+
+    fn ensure_worker(self: &Arc<Self>, printer: String) {
+        if !self.workers.lock().read_or_panic().insert(printer.clone()) {
+            return;
+        }
+
+        let queue = Arc::clone(self);
+        std::thread::spawn(move || {
+            queue.work(&printer);
+        });
+    }
+
+    /// Waits for the next job for `printer` and marks it as printing
+    fn next_job(&self, printer: &str) -> QueuedJob {
+        let mut state = self.state.lock().read_or_panic();
+        loop {
+            if let Some(job) = state.take_next(printer) {
+                return job;
+            }
+            state = self.wake.wait(state).read_or_panic();
+        }
+    }
+
+    /// Waits for a sent job to leave the printer's spooler, so the next job for the printer
+    /// is only sent once this one has printed. Gives up after [`PRINT_TIMEOUT`], and takes the
+    /// job back out of the spooler when it is cancelled meanwhile.
+    fn wait_for_printer(&self, id: u64, sent: &SentPrint) -> QueuedJobStatus {
+        let deadline = Instant::now() + PRINT_TIMEOUT;
+        loop {
+            if self.status(id) != Some(QueuedJobStatus::Printing) {
+                if let Err(err) = printing::cancel_job(&sent.printer, sent.job) {
+                    warn!(
+                        "Could not cancel job {} on {}: {err}",
+                        sent.job, sent.printer.name
+                    );
+                }
+                return QueuedJobStatus::Cancelled;
+            }
+            if !printing::is_job_active(&sent.printer, sent.job) {
+                return QueuedJobStatus::Completed;
+            }
+            if Instant::now() >= deadline {
+                return QueuedJobStatus::TimedOut;
+            }
+
+            // Woken early when the queue changes, so a cancel is acted on straight away
+            let state = self.state.lock().read_or_panic();
+            if state.status(id) == Some(&QueuedJobStatus::Printing) {
+                drop(
+                    self.wake
+                        .wait_timeout(state, JOB_POLL_INTERVAL)
+                        .read_or_panic(),
+                );
+            }
+        }
+    }
+
+    fn work(self: Arc<Self>, printer: &str) {
+        let Some(app_handle) = self.app_handle.get().cloned() else {
+            error!("The print queue has not been started");
+            return;
+        };
+
+        loop {
+            let job = self.next_job(printer);
+            if let Err(err) = self.save(&self.snapshot()) {
+                error!("Could not save the print queue: {err}");
+            }
+
+            info!("Sending print job {} to {printer}", job.name);
+            let status = match send(&app_handle, &job) {
+                Ok(sent) => {
+                    let status = self.wait_for_printer(job.id, &sent);
+                    let history_status = match status {
+                        QueuedJobStatus::Completed => Some(HistoryStatus::Completed),
+                        QueuedJobStatus::Cancelled => Some(HistoryStatus::Cancelled),
+                        _ => None,
+                    };
+                    if let (Some(history_id), Some(history_status)) =
+                        (sent.history_id, history_status)
+                    {
+                        history::set_status(&app_handle, history_id, history_status);
+                    }
+                    if status == QueuedJobStatus::TimedOut {
+                        warn!(
+                            "Print job {} did not finish within {} minutes",
+                            job.name,
+                            PRINT_TIMEOUT.as_secs() / 60
+                        );
+                    }
+                    status
+                }
+                Err(err) => {
+                    error!("Print job {} failed: {err}", job.name);
+                    QueuedJobStatus::Failed { error: err }
+                }
+            };
+            let completed = status == QueuedJobStatus::Completed;
+
+            {
+                let mut state = self.state.lock().read_or_panic();
+                state.finish(job.id, status);
+                state.prune_finished();
+                // How a sent job ended is kept even when it can not be saved, as it can not
+                // be taken back
+                if let Err(err) = self.save(&state) {
+                    error!("Could not save the print queue: {err}");
+                }
+            }
+            self.wake_workers();
+
+            if completed {
+                let _ = app_handle.emit("printing_completed", ());
+            }
+        }
+    }
+
+    /// How a job currently stands, unset once it has been removed from the queue
+    pub fn status(&self, id: u64) -> Option<QueuedJobStatus> {
+        return self.state.lock().read_or_panic().status(id).cloned();
+    }
+
+    /// Blocks until the job has completed, failed, timed out or been cancelled
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the job is removed from the queue before it settles.
+    pub fn wait_for(&self, id: u64) -> Result<QueuedJobStatus, String> {
+        let mut state = self.state.lock().read_or_panic();
+        loop {
+            match state.status(id) {
+                Some(status) if status.is_settled() => return Ok(status.clone()),
+                Some(_) => {}
+                None => return Err(format!("Print job {id} was removed from the queue")),
+            }
+            state = self.wake.wait(state).read_or_panic();
+        }
+    }
+
+    /// Adds a job to the end of the queue, printing to the default printer when none is given
+    pub fn enqueue(
+        self: &Arc<Self>,
+        printer: Option<&str>,
+        documents: Vec<PdfPrintDetails>,
+        combine_options: CombineOptions,
+        print_options: PrintOptions,
+    ) -> Result<u64, String> {
+        let printer = printing::find_printer(printer)?.name;
+        let name = job_name(&documents, &print_options);
+
+        return self.modify(|state| {
+            info!("Queued print job {name} for {printer}");
+            return Ok(state.push(name, printer, documents, combine_options, print_options));
+        });
+    }
+}
+
+fn queue(app_handle: &tauri::AppHandle) -> Arc<PrintQueue> {
+    return Arc::clone(&app_handle.state::<Arc<PrintQueue>>());
+}
+
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+pub fn list_print_queue(app_handle: tauri::AppHandle) -> QueueState {
+    return queue(&app_handle).snapshot();
+}
+
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+pub fn pause_print_queue(app_handle: tauri::AppHandle) -> Result<(), String> {
+    return queue(&app_handle).modify(|state| {
+        state.paused = true;
+        return Ok(());
+    });
+}
+
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+pub fn resume_print_queue(app_handle: tauri::AppHandle) -> Result<(), String> {
+    return queue(&app_handle).modify(|state| {
+        state.paused = false;
+        return Ok(());
+    });
+}
+
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+pub fn hold_print_job(app_handle: tauri::AppHandle, id: u64) -> Result<(), String> {
+    return queue(&app_handle).modify(|state| return state.hold(id));
+}
+
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+pub fn release_print_job(app_handle: tauri::AppHandle, id: u64) -> Result<(), String> {
+    return queue(&app_handle).modify(|state| return state.release(id));
+}
+
+/// Moves a job to `index` in the queue, jobs are sent to each printer in queue order
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+pub fn move_print_job(app_handle: tauri::AppHandle, id: u64, index: usize) -> Result<(), String> {
+    return queue(&app_handle).modify(|state| return state.move_job(id, index));
+}
+
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+pub fn cancel_print_job(app_handle: tauri::AppHandle, id: u64) -> Result<(), String> {
+    return queue(&app_handle).modify(|state| return state.cancel(id));
+}
+
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+pub fn retry_print_job(app_handle: tauri::AppHandle, id: u64) -> Result<(), String> {
+    return queue(&app_handle).modify(|state| return state.retry(id));
+}
+
+/// Removes completed, cancelled, timed out and failed jobs from the queue
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+pub fn clear_finished_print_jobs(app_handle: tauri::AppHandle) -> Result<(), String> {
+    return queue(&app_handle).modify(|state| {
+        state.clear_settled();
+        return Ok(());
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue_of(jobs: &[(&str, &str)]) -> QueueState {
+        let mut state = QueueState::default();
+        for (name, printer) in jobs {
+            state.push(
+                (*name).to_string(),
+                (*printer).to_string(),
+                vec![],
+                CombineOptions::default(),
+                PrintOptions::default(),
+            );
+        }
+        return state;
+    }
+
+    fn next_name(state: &mut QueueState, printer: &str) -> Option<String> {
+        return state.take_next(printer).map(|job| return job.name);
+    }
+
+    #[test]
+    fn sends_jobs_in_order_per_printer() {
+        let mut state = queue_of(&[("a", "office"), ("b", "lab"), ("c", "office")]);
+
+        assert_eq!(next_name(&mut state, "office").as_deref(), Some("a"));
+        assert_eq!(next_name(&mut state, "office").as_deref(), Some("c"));
+        assert_eq!(next_name(&mut state, "office"), None);
+        assert_eq!(next_name(&mut state, "lab").as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn sends_nothing_while_paused() {
+        let mut state = queue_of(&[("a", "office")]);
+
+        state.paused = true;
+        assert_eq!(next_name(&mut state, "office"), None);
+        state.paused = false;
+        assert_eq!(next_name(&mut state, "office").as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn skips_held_jobs_until_released() {
+        let mut state = queue_of(&[("a", "office"), ("b", "office")]);
+
+        assert!(state.hold(1).is_ok());
+        assert!(state.hold(1).is_err());
+        assert_eq!(next_name(&mut state, "office").as_deref(), Some("b"));
+        assert_eq!(next_name(&mut state, "office"), None);
+
+        assert!(state.release(1).is_ok());
+        assert!(state.release(1).is_err());
+        assert_eq!(next_name(&mut state, "office").as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn reorders_jobs() {
+        let mut state = queue_of(&[("a", "office"), ("b", "office"), ("c", "office")]);
+
+        assert!(state.move_job(3, 0).is_ok());
+        assert!(state.move_job(1, 99).is_ok());
+        assert!(state.move_job(4, 0).is_err());
+
+        let names: Vec<&str> = state
+            .jobs
+            .iter()
+            .map(|job| return job.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["c", "b", "a"]);
+        assert_eq!(next_name(&mut state, "office").as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn cancels_queued_and_printing_jobs() {
+        let mut state = queue_of(&[("a", "office"), ("b", "office")]);
+        assert!(state.take_next("office").is_some());
+
+        assert!(state.cancel(1).is_ok());
+        assert!(state.cancel(2).is_ok());
+        assert_eq!(state.status(1), Some(&QueuedJobStatus::Cancelled));
+        assert_eq!(state.status(2), Some(&QueuedJobStatus::Cancelled));
+        assert!(state.cancel(1).is_err());
+        assert!(state.cancel(3).is_err());
+
+        // The worker finishing afterwards does not undo the cancel
+        state.finish(1, QueuedJobStatus::Completed);
+        assert_eq!(state.status(1), Some(&QueuedJobStatus::Cancelled));
+        assert_eq!(next_name(&mut state, "office"), None);
+    }
+
+    #[test]
+    fn retries_only_jobs_that_did_not_print() {
+        let mut state = queue_of(&[("a", "office"), ("b", "office"), ("c", "office")]);
+        for (id, status) in [
+            (
+                1,
+                QueuedJobStatus::Failed {
+                    error: "jammed".to_string(),
+                },
+            ),
+            (2, QueuedJobStatus::TimedOut),
+            (3, QueuedJobStatus::Completed),
+        ] {
+            assert!(state.take_next("office").is_some());
+            state.finish(id, status);
+        }
+
+        assert!(state.retry(1).is_ok());
+        assert!(state.retry(2).is_ok());
+        assert!(state.retry(3).is_err());
+        assert!(state.retry(1).is_err());
+        assert_eq!(next_name(&mut state, "office").as_deref(), Some("a"));
+        assert_eq!(next_name(&mut state, "office").as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn clears_settled_jobs() {
+        let mut state = queue_of(&[("a", "office"), ("b", "office"), ("c", "office")]);
+        assert!(state.take_next("office").is_some());
+        state.finish(
+            1,
+            QueuedJobStatus::Failed {
+                error: "jammed".to_string(),
+            },
+        );
+        assert!(state.cancel(2).is_ok());
+
+        state.clear_settled();
+        let ids: Vec<u64> = state.jobs.iter().map(|job| return job.id).collect();
+        assert_eq!(ids, vec![3]);
+    }
+
+    fn queue_saved_to(queue_json: PathBuf) -> Arc<PrintQueue> {
+        let queue = Arc::new(PrintQueue::default());
+        let _ = queue.queue_json.set(queue_json);
+        return queue;
+    }
+
+    #[test]
+    fn undoes_changes_that_can_not_be_saved() -> Result<(), String> {
+        let dir = tempfile::tempdir().map_err(|e| return e.to_string())?;
+        // A file stands where the folder holding the queue should be
+        let blocked = dir.path().join("blocked");
+        std::fs::write(&blocked, "").map_err(|e| return e.to_string())?;
+
+        let queue = queue_saved_to(blocked.join(PRINT_QUEUE_FILE));
+        let queued = queue.modify(|state| {
+            return Ok(state.push(
+                "a".to_string(),
+                "office".to_string(),
+                vec![],
+                CombineOptions::default(),
+                PrintOptions::default(),
+            ));
+        });
+        assert!(queued.is_err());
+        assert!(queue.snapshot().jobs.is_empty());
+
+        let queue = queue_saved_to(dir.path().join(PRINT_QUEUE_FILE));
+        queue.modify(|state| {
+            state.paused = true;
+            return Ok(());
+        })?;
+        assert!(queue.snapshot().paused);
+        assert!(dir.path().join(PRINT_QUEUE_FILE).exists());
+
+        return Ok(());
+    }
+}
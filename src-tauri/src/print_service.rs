@@ -0,0 +1,533 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{Cursor, Read, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use log::{error, info, warn};
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::pdf::{self, CombineOptions, PdfPrintDetails};
+use pdfprint_core::printing::{self, PrintOptions};
+use pdfprint_core::range;
+use tauri::Manager;
+use tempfile::{NamedTempFile, TempPath};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::mutex_utils::LockResultExt;
+use crate::print_queue::{PrintQueue, QueuedJobStatus};
+use crate::settings::PrintServiceSettings;
+
+/// Largest PDF accepted as an upload
+const MAX_UPLOAD_BYTES: u64 = 100 * 1024 * 1024;
+/// Finished jobs kept around for status polling
+const MAX_FINISHED_JOBS: usize = 200;
+
+/// A document in a JSON job
+#[derive(serde::Deserialize)]
+struct ServiceDocument {
+    path: String,
+    /// 1-based page range such as `1-3,5`, all pages when unset
+    pages: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ServiceJobRequest {
+    documents: Vec<ServiceDocument>,
+    printer: Option<String>,
+    #[serde(default)]
+    print_options: PrintOptions,
+    #[serde(default)]
+    combine_options: CombineOptions,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct JobState {
+    id: String,
+    #[serde(flatten)]
+    status: QueuedJobStatus,
+}
+
+/// A job's last known status, with its print queue id once it has been queued
+struct TrackedJob {
+    status: QueuedJobStatus,
+    queue_id: Option<u64>,
+}
+
+#[derive(Default)]
+struct JobTable {
+    jobs: HashMap<String, TrackedJob>,
+    finished: VecDeque<String>,
+}
+
+impl JobTable {
+    fn set(&mut self, id: &str, status: QueuedJobStatus) {
+        if status.is_settled() {
+            self.finished.push_back(id.to_string());
+            while self.finished.len() > MAX_FINISHED_JOBS {
+                if let Some(oldest) = self.finished.pop_front() {
+                    self.jobs.remove(&oldest);
+                }
+            }
+        }
+        let queue_id = self.jobs.get(id).and_then(|job| return job.queue_id);
+        self.jobs
+            .insert(id.to_string(), TrackedJob { status, queue_id });
+    }
+
+    fn set_queue_id(&mut self, id: &str, queue_id: u64) {
+        if let Some(job) = self.jobs.get_mut(id) {
+            job.queue_id = Some(queue_id);
+        }
+    }
+
+    /// The status of a job, taken from the print queue while it is still there
+    fn status(&self, id: &str, queue: &PrintQueue) -> Option<QueuedJobStatus> {
+        let job = self.jobs.get(id)?;
+        if job.status.is_settled() {
+            return Some(job.status.clone());
+        }
+
+        return Some(
+            job.queue_id
+                .and_then(|queue_id| return queue.status(queue_id))
+                .unwrap_or_else(|| return job.status.clone()),
+        );
+    }
+}
+
+/// A job accepted by the service, with any uploaded file kept alive until it has settled
+struct AcceptedJob {
+    id: String,
+    documents: Vec<PdfPrintDetails>,
+    printer: Option<String>,
+    print_options: PrintOptions,
+    combine_options: CombineOptions,
+    upload: Option<TempPath>,
+}
+
+struct Running {
+    server: Arc<Server>,
+    thread: JoinHandle<()>,
+    settings: PrintServiceSettings,
+}
+
+/// Local server accepting print jobs from other apps on the same machine
+#[derive(Default)]
+pub struct PrintService {
+    running: Mutex<Option<Running>>,
+    jobs: Arc<Mutex<JobTable>>,
+}
+
+fn header(field: &str, value: &str) -> Option<Header> {
+    return Header::from_bytes(field.as_bytes(), value.as_bytes()).ok();
+}
+
+fn json_response<T: serde::Serialize>(status: u16, body: &T) -> Response<Cursor<Vec<u8>>> {
+    let data = serde_json::to_vec(body).unwrap_or_default();
+    let response = Response::from_data(data).with_status_code(status);
+
+    return match header("Content-Type", "application/json") {
+        Some(content_type) => response.with_header(content_type),
+        None => response,
+    };
+}
+
+fn error_response(status: u16, message: &str) -> Response<Cursor<Vec<u8>>> {
+    return json_response(status, &serde_json::json!({ "error": message }));
+}
+
+/// Compares without bailing out early so the token cannot be guessed from response times
+fn tokens_match(expected: &str, given: &str) -> bool {
+    if expected.len() != given.len() {
+        return false;
+    }
+
+    return expected
+        .bytes()
+        .zip(given.bytes())
+        .fold(0, |acc, (a, b)| return acc | (a ^ b))
+        == 0;
+}
+
+fn is_authorized(request: &Request, token: &str) -> bool {
+    return !token.is_empty()
+        && request.headers().iter().any(|header| {
+            return header.field.equiv("Authorization")
+                && header
+                    .value
+                    .as_str()
+                    .strip_prefix("Bearer ")
+                    .is_some_and(|given| return tokens_match(token, given.trim()));
+        });
+}
+
+fn read_body(request: &mut Request) -> Result<Vec<u8>, String> {
+    let mut body = vec![];
+    request
+        .as_reader()
+        .take(MAX_UPLOAD_BYTES + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| return e.to_string())?;
+
+    if body.len() as u64 > MAX_UPLOAD_BYTES {
+        return Err("Request body is too large".to_string());
+    }
+
+    return Ok(body);
+}
+
+fn to_print_details(
+    document: ServiceDocument,
+    passwords: &Passwords,
+) -> Result<PdfPrintDetails, String> {
+    let path = PathBuf::from(&document.path);
+    let metadata =
+        std::fs::metadata(&path).map_err(|e| return format!("{}: {e}", document.path))?;
+    let pages =
+        pdf::count_pages(&path, passwords).map_err(|e| return format!("{}: {e}", document.path))?;
+    let print_range = document
+        .pages
+        .map(|spec| return range::parse_page_range(&spec, pages))
+        .transpose()
+        .map_err(|e| return format!("{}: {e}", document.path))?;
+
+    return Ok(PdfPrintDetails {
+        name: path.file_name().map_or_else(String::new, |name| {
+            return name.to_string_lossy().to_string();
+        }),
+        pages,
+        size: metadata.len(),
+        path: document.path,
+        print_range,
+        collate: None,
+        select: None,
+        redactions: vec![],
+    });
+}
+
+fn parse_json_job(id: String, body: &[u8], passwords: &Passwords) -> Result<AcceptedJob, String> {
+    let job: ServiceJobRequest = serde_json::from_slice(body).map_err(|e| return e.to_string())?;
+    if job.documents.is_empty() {
+        return Err("A job needs at least one document".to_string());
+    }
+
+    let documents = job
+        .documents
+        .into_iter()
+        .map(|document| return to_print_details(document, passwords))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    return Ok(AcceptedJob {
+        id,
+        documents,
+        printer: job.printer,
+        print_options: job.print_options,
+        combine_options: job.combine_options,
+        upload: None,
+    });
+}
+
+/// Uploads carry the printer and options in the query string, e.g. `?printer=Front&copies=2`
+fn parse_upload_job(
+    id: String,
+    query: &str,
+    body: &[u8],
+    passwords: &Passwords,
+) -> Result<AcceptedJob, String> {
+    if !body.starts_with(b"%PDF") {
+        return Err("Uploads have to be PDF files".to_string());
+    }
+
+    let mut printer = None;
+    let mut print_options = PrintOptions::default();
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "printer" => printer = Some(value.to_string()),
+            "copies" => {
+                print_options.copies = Some(
+                    value
+                        .parse()
+                        .map_err(|_| return format!("Invalid copies: {value}"))?,
+                );
+            }
+            "job_name" => print_options.job_name = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    let mut file = NamedTempFile::with_suffix(".pdf").map_err(|e| return e.to_string())?;
+    file.write_all(body).map_err(|e| return e.to_string())?;
+    let upload = file.into_temp_path();
+    let path = upload.to_string_lossy().to_string();
+    let pages = pdf::count_pages(&upload, passwords)?;
+
+    return Ok(AcceptedJob {
+        id,
+        documents: vec![PdfPrintDetails {
+            name: "upload.pdf".to_string(),
+            pages,
+            size: body.len() as u64,
+            path,
+            print_range: None,
+            collate: None,
+            select: None,
+            redactions: vec![],
+        }],
+        printer,
+        print_options,
+        combine_options: CombineOptions::default(),
+        upload: Some(upload),
+    });
+}
+
+/// Queues the job and waits for the queue to settle it
+fn run_job(app_handle: &tauri::AppHandle, job: AcceptedJob, jobs: &Mutex<JobTable>) {
+    let queue = app_handle.state::<Arc<PrintQueue>>();
+    let queued = queue.enqueue(
+        job.printer.as_deref(),
+        job.documents,
+        job.combine_options,
+        job.print_options,
+    );
+    let result = queued.and_then(|queue_id| {
+        jobs.lock().read_or_panic().set_queue_id(&job.id, queue_id);
+        return queue.wait_for(queue_id);
+    });
+
+    let status = result.unwrap_or_else(|error| return QueuedJobStatus::Failed { error });
+    match &status {
+        QueuedJobStatus::Failed { error } => warn!("Print service job {} failed: {error}", job.id),
+        QueuedJobStatus::TimedOut => warn!("Print service job {} timed out", job.id),
+        _ => info!("Print service job {} finished", job.id),
+    }
+
+    // The printer may still read an upload that timed out, so it is left behind rather than
+    // deleted from under the spooler
+    if let (QueuedJobStatus::TimedOut, Some(upload)) = (&status, job.upload) {
+        match upload.keep() {
+            Ok(path) => info!(
+                "Kept upload {} for the unfinished job",
+                path.to_string_lossy()
+            ),
+            Err(err) => warn!("Could not keep the upload for the unfinished job: {err}"),
+        }
+    }
+    jobs.lock().read_or_panic().set(&job.id, status);
+}
+
+impl PrintService {
+    fn accept(&self, app_handle: &tauri::AppHandle, job: AcceptedJob) -> Response<Cursor<Vec<u8>>> {
+        let state = JobState {
+            id: job.id.clone(),
+            status: QueuedJobStatus::Queued,
+        };
+        self.jobs
+            .lock()
+            .read_or_panic()
+            .set(&job.id, QueuedJobStatus::Queued);
+
+        let jobs = Arc::clone(&self.jobs);
+        let app_handle = app_handle.clone();
+        std::thread::spawn(move || {
+            run_job(&app_handle, job, &jobs);
+        });
+
+        return json_response(202, &state);
+    }
+
+    fn handle(&self, app_handle: &tauri::AppHandle, mut request: Request, token: &str) {
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((url.as_str(), ""));
+        let method = request.method().clone();
+
+        let response = if is_authorized(&request, token) {
+            match (&method, path) {
+                (Method::Get, "/printers") => json_response(200, &printing::list_printers()),
+                (Method::Post, "/jobs" | "/jobs/pdf") => {
+                    let id = uuid::Uuid::new_v4().to_string();
+                    let passwords = app_handle.state::<Passwords>();
+                    let job = read_body(&mut request).and_then(|body| {
+                        if path == "/jobs" {
+                            return parse_json_job(id, &body, &passwords);
+                        }
+                        return parse_upload_job(id, query, &body, &passwords);
+                    });
+
+                    match job {
+                        Ok(job) => self.accept(app_handle, job),
+                        Err(err) => error_response(400, &err),
+                    }
+                }
+                (Method::Get, _) if path.starts_with("/jobs/") => {
+                    let id = path.trim_start_matches("/jobs/");
+                    let queue = app_handle.state::<Arc<PrintQueue>>();
+                    let status = self.jobs.lock().read_or_panic().status(id, &queue);
+
+                    match status {
+                        Some(status) => json_response(
+                            200,
+                            &JobState {
+                                id: id.to_string(),
+                                status,
+                            },
+                        ),
+                        None => error_response(404, "Unknown job"),
+                    }
+                }
+                _ => error_response(404, "Not found"),
+            }
+        } else {
+            error_response(401, "Missing or invalid token")
+        };
+
+        if let Err(err) = request.respond(response) {
+            warn!("Could not respond to print service request: {err}");
+        }
+    }
+
+    fn stop(&self) {
+        let running = self.running.lock().read_or_panic().take();
+
+        if let Some(running) = running {
+            running.server.unblock();
+            if running.thread.join().is_err() {
+                error!("Print service thread panicked");
+            }
+            #[cfg(unix)]
+            {
+                if let Some(socket_path) = running.settings.socket_path {
+                    let _ = std::fs::remove_file(socket_path);
+                }
+            }
+            info!("Print service stopped");
+        }
+    }
+
+    fn bind(settings: &PrintServiceSettings) -> Result<Server, String> {
+        #[cfg(unix)]
+        {
+            if let Some(socket_path) = &settings.socket_path {
+                use std::os::unix::fs::PermissionsExt;
+
+                let socket_path = std::path::Path::new(socket_path);
+                // A socket left behind by a previous run would make binding fail
+                let _ = std::fs::remove_file(socket_path);
+
+                // Bound inside a folder only the owner can enter and moved into place once its
+                // own permissions are restricted, so it is never reachable with the default umask
+                let folder = socket_path
+                    .parent()
+                    .filter(|parent| return !parent.as_os_str().is_empty())
+                    .unwrap_or_else(|| return std::path::Path::new("."));
+                let private = tempfile::Builder::new()
+                    .prefix(".print-service")
+                    .tempdir_in(folder)
+                    .map_err(|e| return e.to_string())?;
+                let bound_path = private.path().join("socket");
+                let server = Server::http_unix(&bound_path).map_err(|e| return e.to_string())?;
+                std::fs::set_permissions(&bound_path, std::fs::Permissions::from_mode(0o600))
+                    .map_err(|e| return e.to_string())?;
+                std::fs::rename(&bound_path, socket_path).map_err(|e| return e.to_string())?;
+
+                return Ok(server);
+            }
+        }
+
+        return Server::http(("127.0.0.1", settings.port)).map_err(|e| return e.to_string());
+    }
+
+    /// Starts, restarts or stops the server so it matches the settings
+    pub fn configure(
+        self: &Arc<Self>,
+        app_handle: &tauri::AppHandle,
+        settings: &PrintServiceSettings,
+    ) -> Result<(), String> {
+        let unchanged = self
+            .running
+            .lock()
+            .read_or_panic()
+            .as_ref()
+            .is_some_and(|running| return &running.settings == settings);
+        if unchanged {
+            return Ok(());
+        }
+
+        self.stop();
+
+        if !settings.enabled {
+            return Ok(());
+        }
+        if settings.token.is_empty() {
+            return Err("The print service needs a token".to_string());
+        }
+
+        let server = Arc::new(Self::bind(settings)?);
+        let token = settings.token.clone();
+        let service = Arc::clone(self);
+        let thread_server = Arc::clone(&server);
+        let handle = app_handle.clone();
+        let thread = std::thread::spawn(move || {
+            for request in thread_server.incoming_requests() {
+                service.handle(&handle, request, &token);
+            }
+        });
+
+        info!("Print service listening on {}", server.server_addr());
+        *self.running.lock().read_or_panic() = Some(Running {
+            server,
+            thread,
+            settings: settings.clone(),
+        });
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_wrong_or_missing_tokens() {
+        assert!(tokens_match("secret", "secret"));
+        assert!(!tokens_match("secret", "secreT"));
+        assert!(!tokens_match("secret", "secret2"));
+        assert!(!tokens_match("secret", ""));
+    }
+
+    #[test]
+    fn rejects_malformed_json_jobs() {
+        let passwords = Passwords::default();
+        let parse = |body: &str| {
+            return parse_json_job("job".to_string(), body.as_bytes(), &passwords).err();
+        };
+
+        assert!(parse("{\"documents\": [").is_some());
+        assert!(parse("{\"documents\": [{\"pages\": \"1-2\"}]}").is_some());
+        assert_eq!(
+            parse("{\"documents\": []}"),
+            Some("A job needs at least one document".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_uploads() {
+        let passwords = Passwords::default();
+        let parse = |query: &str, body: &[u8]| {
+            return parse_upload_job("job".to_string(), query, body, &passwords).err();
+        };
+
+        // A multipart form rather than the PDF itself
+        let multipart =
+            b"--boundary\r\nContent-Disposition: form-data; name=\"file\"\r\n\r\n%PDF-1.7";
+        assert_eq!(
+            parse("", multipart),
+            Some("Uploads have to be PDF files".to_string())
+        );
+        assert_eq!(
+            parse("copies=many", b"%PDF-1.7"),
+            Some("Invalid copies: many".to_string())
+        );
+        assert!(parse("", b"%PDF-1.7 truncated").is_some());
+    }
+}
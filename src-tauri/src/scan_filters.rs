@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind};
+use std::path::Path;
+
+use log::{error, info};
+use pdfprint_core::scan_filter::ScanFilters;
+
+use crate::persistence::{self, write_json_atomic};
+
+/// Stored next to `workspace.json`, holding the filters of every workspace keyed by its path
+pub const SCAN_FILTERS_FILE: &str = "scan_filters.json";
+
+/// Reads the filters of every workspace. A corrupt file is backed up rather than treated as
+/// empty, so the next save does not wipe the filters of the other workspaces.
+fn read_all(filters_json: &Path) -> Result<HashMap<String, ScanFilters>, String> {
+    let file = match File::open(filters_json) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(err) => {
+            return Err(format!(
+                "Could not read {}: {err}",
+                filters_json.to_string_lossy()
+            ))
+        }
+    };
+
+    return serde_json::from_reader(BufReader::new(file)).map_err(|err| {
+        error!("Could not parse {}: {err}", filters_json.to_string_lossy());
+        if let Ok(backup) = persistence::backup_corrupt(filters_json) {
+            info!(
+                "Moved unreadable scan filters to {}",
+                backup.to_string_lossy()
+            );
+        }
+        return format!("Could not load scan filters: {err}");
+    });
+}
+
+/// Loads the filters stored for a workspace, falling back to the defaults when it has none
+pub fn load_scan_filters(filters_json: &Path, workspace: &str) -> Result<ScanFilters, String> {
+    return Ok(read_all(filters_json)?
+        .remove(workspace)
+        .unwrap_or_default());
+}
+
+pub fn save_scan_filters(
+    filters_json: &Path,
+    workspace: &str,
+    filters: ScanFilters,
+) -> Result<(), String> {
+    let mut all = read_all(filters_json)?;
+    all.insert(workspace.to_string(), filters);
+
+    return write_json_atomic(filters_json, &all);
+}
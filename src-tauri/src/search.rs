@@ -0,0 +1,126 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use log::{error, info};
+use pdfprint_core::passwords::Passwords;
+use pdfprint_core::text_index::{SearchHit, TextIndex};
+use tauri::{Emitter, Manager};
+
+use crate::mutex_utils::LockResultExt;
+use crate::persistence;
+use crate::{get_app_data_dir, get_scan_filter, get_workspace_root};
+
+const TEXT_INDEX_FILE: &str = "text_index.json";
+const TEXT_INDEX_VERSION: u32 = 1;
+/// Hits returned by a single search
+const MAX_SEARCH_HITS: usize = 200;
+
+/// Text of the workspace documents, kept up to date in the background as folders are scanned
+#[derive(Default)]
+pub struct SearchIndex {
+    /// Loaded from disk on first use
+    index: Mutex<Option<TextIndex>>,
+    indexing: AtomicBool,
+    /// Set when a refresh is asked for while one is already running
+    pending: AtomicBool,
+}
+
+fn load(index_json: &Path) -> TextIndex {
+    return match persistence::read_versioned(index_json, TEXT_INDEX_VERSION, &[]) {
+        Ok(index) => index.unwrap_or_default(),
+        Err(err) => {
+            // The index is rebuilt from the documents, so nothing is lost by starting over
+            error!("Could not load the search index: {err}");
+            if err.is_corrupt() {
+                if let Ok(backup) = persistence::backup_corrupt(index_json) {
+                    info!(
+                        "Moved unreadable search index to {}",
+                        backup.to_string_lossy()
+                    );
+                }
+            }
+            TextIndex::default()
+        }
+    };
+}
+
+impl SearchIndex {
+    fn snapshot(&self, app_handle: &tauri::AppHandle) -> Result<TextIndex, String> {
+        let index_json = get_app_data_dir(app_handle)?.join(TEXT_INDEX_FILE);
+        let mut index = self.index.lock().read_or_panic();
+
+        return Ok(index
+            .get_or_insert_with(|| return load(&index_json))
+            .clone());
+    }
+
+    /// Updates the index for the active workspace in a background thread
+    pub fn refresh(self: &Arc<Self>, app_handle: &tauri::AppHandle) {
+        self.pending.store(true, Ordering::SeqCst);
+        if self.indexing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let search_index = Arc::clone(self);
+        let app_handle = app_handle.clone();
+        std::thread::spawn(move || {
+            loop {
+                while search_index.pending.swap(false, Ordering::SeqCst) {
+                    if let Err(err) = search_index.index_workspace(&app_handle) {
+                        error!("Could not update the search index: {err}");
+                    }
+                }
+                search_index.indexing.store(false, Ordering::SeqCst);
+
+                // A refresh asked for after the last check would otherwise be lost
+                if !search_index.pending.load(Ordering::SeqCst)
+                    || search_index.indexing.swap(true, Ordering::SeqCst)
+                {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn index_workspace(&self, app_handle: &tauri::AppHandle) -> Result<(), String> {
+        let root = get_workspace_root(app_handle)?;
+        let root = Path::new(&root);
+        let filter = get_scan_filter(app_handle, root);
+
+        // Searches keep using the previous index while text is extracted
+        let mut index = self.snapshot(app_handle)?;
+        let update = index.update(root, &filter, &app_handle.state::<Passwords>());
+        if update.indexed == 0 && update.removed == 0 {
+            return Ok(());
+        }
+        info!(
+            "Indexed {} documents, removed {}, {} failed",
+            update.indexed, update.removed, update.failed
+        );
+
+        let index_json = get_app_data_dir(app_handle)?.join(TEXT_INDEX_FILE);
+        persistence::write_versioned(&index_json, TEXT_INDEX_VERSION, &index)?;
+        *self.index.lock().read_or_panic() = Some(index);
+        let _ = app_handle.emit("search-index-updated", update);
+
+        return Ok(());
+    }
+}
+
+/// Finds the pages of documents in the active workspace containing `query`
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+pub fn search_workspace(
+    app_handle: tauri::AppHandle,
+    query: String,
+) -> Result<Vec<SearchHit>, String> {
+    let root = get_workspace_root(&app_handle)?;
+    let search_index = app_handle.state::<Arc<SearchIndex>>();
+    let index_json = get_app_data_dir(&app_handle)?.join(TEXT_INDEX_FILE);
+
+    let mut index = search_index.index.lock().read_or_panic();
+    return Ok(index
+        .get_or_insert_with(|| return load(&index_json))
+        .search(Path::new(&root), &query, MAX_SEARCH_HITS));
+}
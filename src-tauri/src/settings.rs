@@ -0,0 +1,166 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use log::{error, info};
+use pdfprint_core::pdf::CombineOptions;
+use pdfprint_core::printing::PrintOptions;
+use tauri::Manager;
+
+use crate::get_app_data_dir;
+use crate::hot_folder::HotFolder;
+use crate::mutex_utils::LockResultExt;
+use crate::persistence;
+use crate::print_service::PrintService;
+
+const SETTINGS_FILE: &str = "settings.json";
+const SETTINGS_VERSION: u32 = 1;
+
+/// Port the print service listens on when none is configured
+const DEFAULT_SERVICE_PORT: u16 = 17_917;
+
+/// Local print service accepting jobs from other apps, off unless explicitly enabled
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct PrintServiceSettings {
+    pub enabled: bool,
+    /// Port on 127.0.0.1 to listen on
+    pub port: u16,
+    /// Unix socket to listen on instead of the TCP port
+    pub socket_path: Option<String>,
+    /// Bearer token clients have to send, generated when the service is first enabled
+    pub token: String,
+}
+
+impl Default for PrintServiceSettings {
+    fn default() -> Self {
+        return Self {
+            enabled: false,
+            port: DEFAULT_SERVICE_PORT,
+            socket_path: None,
+            token: String::new(),
+        };
+    }
+}
+
+/// Folder whose files are printed automatically once they finish being written
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct HotFolderSettings {
+    pub enabled: bool,
+    pub path: Option<String>,
+    /// Printer to use, the default printer when unset
+    pub printer: Option<String>,
+    pub print_options: PrintOptions,
+    pub combine_options: CombineOptions,
+    /// Seconds between checks of the folder
+    pub poll_seconds: u64,
+    /// Checks a file's size and modification time have to stay the same before it is printed
+    pub stable_checks: u32,
+}
+
+impl Default for HotFolderSettings {
+    fn default() -> Self {
+        return Self {
+            enabled: false,
+            path: None,
+            printer: None,
+            print_options: PrintOptions::default(),
+            combine_options: CombineOptions::default(),
+            poll_seconds: 2,
+            stable_checks: 2,
+        };
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub print_service: PrintServiceSettings,
+    pub hot_folder: HotFolderSettings,
+}
+
+pub fn generate_token() -> String {
+    return uuid::Uuid::new_v4().simple().to_string();
+}
+
+/// Reads the settings file, using defaults when it cannot be read and backing it up when it is corrupt
+pub fn load_settings(app_data: &Path) -> Settings {
+    let settings_json = app_data.join(SETTINGS_FILE);
+
+    return match persistence::read_versioned(&settings_json, SETTINGS_VERSION, &[]) {
+        Ok(settings) => settings.unwrap_or_default(),
+        Err(err) => {
+            error!("Could not load settings: {err}");
+            if err.is_corrupt() {
+                if let Ok(backup) = persistence::backup_corrupt(&settings_json) {
+                    info!("Moved unreadable settings to {}", backup.to_string_lossy());
+                }
+            }
+            Settings::default()
+        }
+    };
+}
+
+/// Starts or stops the background services to match the settings, each service is
+/// configured even when the other one fails
+pub fn apply_settings(app_handle: &tauri::AppHandle, settings: &Settings) -> Result<(), String> {
+    let print_service = app_handle
+        .state::<Arc<PrintService>>()
+        .configure(app_handle, &settings.print_service)
+        .map_err(|err| return format!("Could not start the print service: {err}"));
+    let hot_folder = app_handle
+        .state::<HotFolder>()
+        .configure(app_handle, &settings.hot_folder)
+        .map_err(|err| return format!("Could not watch the hot folder: {err}"));
+
+    let errors: Vec<String> = [print_service.err(), hot_folder.err()]
+        .into_iter()
+        .flatten()
+        .collect();
+    if !errors.is_empty() {
+        return Err(errors.join(", "));
+    }
+
+    return Ok(());
+}
+
+#[tauri::command]
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_settings(app_handle: tauri::AppHandle) -> Settings {
+    return app_handle
+        .state::<Mutex<Settings>>()
+        .lock()
+        .read_or_panic()
+        .clone();
+}
+
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::needless_pass_by_value)]
+pub fn update_settings(
+    app_handle: tauri::AppHandle,
+    mut settings: Settings,
+) -> Result<Settings, String> {
+    if settings.print_service.enabled && settings.print_service.token.is_empty() {
+        settings.print_service.token = generate_token();
+    }
+
+    let settings_state = app_handle.state::<Mutex<Settings>>();
+    let mut current = settings_state.lock().read_or_panic();
+
+    // Only settings the services actually started with are saved, otherwise the services
+    // go back to how they were
+    let applied = apply_settings(&app_handle, &settings).and_then(|()| {
+        let settings_json = get_app_data_dir(&app_handle)?.join(SETTINGS_FILE);
+        return persistence::write_versioned(&settings_json, SETTINGS_VERSION, &settings);
+    });
+    if let Err(err) = applied {
+        if let Err(restore_err) = apply_settings(&app_handle, &current) {
+            error!("Could not restore the previous settings: {restore_err}");
+        }
+        return Err(err);
+    }
+
+    *current = settings.clone();
+
+    return Ok(settings);
+}
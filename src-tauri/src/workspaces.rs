@@ -0,0 +1,160 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::persistence::Migration;
+
+/// Version written to `workspace.json`, bump it and add a migration when the format changes
+pub const STATE_VERSION: u32 = 1;
+
+/// Migrations for `workspace.json`, indexed by the version they upgrade from
+pub const MIGRATIONS: [Migration; 1] = [migrate_v0_to_v1];
+
+/// How many workspaces are kept in the "Open Recent" history
+const MAX_RECENT: usize = 10;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AppState {
+    pub workspaces: Vec<Workspace>,
+    /// Path of the workspace currently shown
+    pub active: Option<String>,
+    /// Most recently used workspace paths, newest first
+    pub recent: Vec<String>,
+}
+
+/// Version 0 held a single `workspace` path
+fn migrate_v0_to_v1(mut json: Value) -> Result<Value, String> {
+    let legacy = json
+        .as_object_mut()
+        .and_then(|object| return object.remove("workspace"));
+    let mut state: AppState = serde_json::from_value(json).map_err(|e| return e.to_string())?;
+
+    if let Some(Value::String(path)) = legacy {
+        state.add(&path, None);
+        state.activate(&path);
+    }
+
+    return serde_json::to_value(state).map_err(|e| return e.to_string());
+}
+
+fn default_name(path: &str) -> String {
+    return Path::new(path).file_name().map_or_else(
+        || return path.to_string(),
+        |name| return name.to_string_lossy().to_string(),
+    );
+}
+
+impl AppState {
+    pub fn find(&self, path: &str) -> Option<&Workspace> {
+        return self
+            .workspaces
+            .iter()
+            .find(|workspace| return workspace.path == path);
+    }
+
+    /// Adds a workspace, keeping the existing entry if the path is already known
+    pub fn add(&mut self, path: &str, name: Option<String>) {
+        if self.find(path).is_some() {
+            return;
+        }
+
+        self.workspaces.push(Workspace {
+            name: name.unwrap_or_else(|| return default_name(path)),
+            path: path.to_string(),
+        });
+    }
+
+    pub fn remove(&mut self, path: &str) -> Result<(), String> {
+        if self.find(path).is_none() {
+            return Err(format!("Unknown workspace: {path}"));
+        }
+
+        self.workspaces
+            .retain(|workspace| return workspace.path != path);
+        self.recent.retain(|recent| return recent != path);
+        if self.active.as_deref() == Some(path) {
+            self.active = None;
+        }
+
+        return Ok(());
+    }
+
+    pub fn rename(&mut self, path: &str, name: String) -> Result<(), String> {
+        let workspace = self
+            .workspaces
+            .iter_mut()
+            .find(|workspace| return workspace.path == path)
+            .ok_or_else(|| return format!("Unknown workspace: {path}"))?;
+        workspace.name = name;
+
+        return Ok(());
+    }
+
+    /// Makes a known workspace active and moves it to the front of the recent list
+    pub fn activate(&mut self, path: &str) -> bool {
+        if self.find(path).is_none() {
+            return false;
+        }
+
+        self.active = Some(path.to_string());
+        self.recent.retain(|recent| return recent != path);
+        self.recent.insert(0, path.to_string());
+        self.recent.truncate(MAX_RECENT);
+
+        return true;
+    }
+
+    pub fn clear_recent(&mut self) {
+        self.recent.clear();
+    }
+
+    /// Recent workspaces that are still known, newest first
+    pub fn recent_workspaces(&self) -> Vec<&Workspace> {
+        return self
+            .recent
+            .iter()
+            .filter_map(|path| return self.find(path))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_the_single_workspace() -> Result<(), String> {
+        let migrated = migrate_v0_to_v1(serde_json::json!({ "workspace": "/scans/2024" }))?;
+        let state: AppState = serde_json::from_value(migrated).map_err(|e| return e.to_string())?;
+
+        assert_eq!(
+            state.workspaces,
+            vec![Workspace {
+                name: "2024".to_string(),
+                path: "/scans/2024".to_string(),
+            }]
+        );
+        assert_eq!(state.active.as_deref(), Some("/scans/2024"));
+        assert_eq!(state.recent, vec!["/scans/2024".to_string()]);
+
+        return Ok(());
+    }
+
+    #[test]
+    fn migrates_files_without_a_workspace() -> Result<(), String> {
+        let migrated = migrate_v0_to_v1(serde_json::json!({}))?;
+        let state: AppState = serde_json::from_value(migrated).map_err(|e| return e.to_string())?;
+
+        assert!(state.workspaces.is_empty());
+        assert_eq!(state.active, None);
+
+        return Ok(());
+    }
+}
@@ -1,6 +1,6 @@
 import { createColumnHelper, RowSelectionState, Updater } from '@tanstack/react-table';
 import { invoke } from '@tauri-apps/api/core';
-import { open, save } from '@tauri-apps/plugin-dialog';
+import { message, open, save } from '@tauri-apps/plugin-dialog';
 
 import { useAtomValue } from 'jotai';
 import { useAtomCallback } from 'jotai/utils';
@@ -10,7 +10,15 @@ import { Button } from './components/button/Button';
 import { EmptyState } from './components/emptyState/EmptyState';
 import { Heading } from './components/heading/Heading';
 import { DataTable } from './components/table/DataTable';
-import { Entries, EntriesWithChildren, groupedPdfsAtom, loadedDirsAtom, pdfAtom, pdfsByIdAtom } from './store';
+import {
+  Entries,
+  EntriesWithChildren,
+  entriesAtom,
+  groupedPdfsAtom,
+  loadedDirsAtom,
+  LOOSE_FILES_ID,
+  pdfsByIdAtom,
+} from './store';
 import { parsePrintRange } from './utils/parse-print-range';
 
 const columnHelper = createColumnHelper<EntriesWithChildren>();
@@ -80,7 +88,7 @@ const serializePdfs = (pdfs: string[], pdfsById: Record<string, Entries> | undef
 };
 
 function App() {
-  const pdfs = useAtomValue(pdfAtom);
+  const pdfs = useAtomValue(entriesAtom);
   const grouped = useAtomValue(groupedPdfsAtom);
   const pdfsById = useAtomValue(pdfsByIdAtom);
   const isInitial = pdfs == null || grouped == null;
@@ -113,6 +121,8 @@ function App() {
         void invoke('save_to_file', {
           file: file,
           pdfs: serializePdfs(data, pdfsById),
+        }).catch((error: unknown) => {
+          void message(String(error), { title: 'Could not save the PDF', kind: 'error' });
         });
         setData([]);
       }
@@ -145,7 +155,8 @@ function App() {
             .map((id) => pdfsById[id])
             .filter((entry): entry is Entries => {
               const loaded = (entry != null && loadedPdfs[entry.id.toString()]) ?? false;
-              return entry != null && entry.type === 'dir' && !loaded;
+              // Loose files are sent by the backend as a whole rather than loaded per folder
+              return entry != null && entry.type === 'dir' && entry.id !== LOOSE_FILES_ID && !loaded;
             })
             .map((entry) => {
               return invoke('load_dir', { folder: entry.path }).then(() => {
@@ -1,7 +1,19 @@
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
+import { message } from '@tauri-apps/plugin-dialog';
 import { atom, createStore } from 'jotai';
 
+interface PdfMetadata {
+  title?: string | null;
+  author?: string | null;
+  subject?: string | null;
+  keywords?: string | null;
+  producer?: string | null;
+  created?: string | null; // RFC 3339
+  modified?: string | null; // RFC 3339
+  version?: string | null;
+}
+
 interface PdfDetails {
   parent?: number | null;
   printRange?: string; // Gets updated from the table meta
@@ -10,6 +22,12 @@ interface PdfDetails {
   path: string;
   size: number;
   type: 'pdf';
+  format: 'pdf' | 'image' | 'text';
+  encrypted: boolean;
+  locked: boolean; // Pages stay at 0 until unlock_pdf succeeds
+  metadata: PdfMetadata;
+  damaged: boolean; // Could not be opened, or only after being rebuilt; repair_pdf writes a clean copy
+  warnings: string[];
   id: number;
 }
 interface Dir {
@@ -25,9 +43,24 @@ export type Entries = PdfDetails | Dir;
 export type EntriesWithChildren = DirWithChildren | PdfDetails;
 export type DirWithChildren = Dir & { children: EntriesWithChildren[] };
 
+// Matches LOOSE_FILES_ID on the backend, the pseudo folder dropped and opened files are listed under
+export const LOOSE_FILES_ID = 0;
+
 export const pdfAtom = atom<Entries[] | null>(null);
-export const groupedPdfsAtom = atom((get) => {
+export const looseFilesAtom = atom<Entries[]>([]);
+// Workspace entries together with loose files, which stay around when the workspace changes
+export const entriesAtom = atom((get) => {
   const pdfs = get(pdfAtom);
+  const looseFiles = get(looseFilesAtom);
+
+  if (pdfs == null && looseFiles.length === 0) {
+    return null;
+  }
+
+  return [...looseFiles, ...(pdfs ?? [])];
+});
+export const groupedPdfsAtom = atom((get) => {
+  const pdfs = get(entriesAtom);
 
   if (pdfs == null) {
     return null;
@@ -65,7 +98,7 @@ export const groupedPdfsAtom = atom((get) => {
   return [...map.values()];
 });
 export const pdfsByIdAtom = atom((get) => {
-  const pdfs = get(pdfAtom);
+  const pdfs = get(entriesAtom);
 
   return pdfs?.reduce(
     (acc, pdf) => {
@@ -88,6 +121,29 @@ const setupEvents = async (store: ReturnType<typeof createStore>) => {
       });
     });
 
+    await listen<string | null>('workspace-changed', (event) => {
+      // Entries from the previous workspace are appended to otherwise
+      store.set(pdfAtom, event.payload == null ? null : []);
+      store.set(loadedDirsAtom, {});
+    });
+
+    await listen<string>('scan-filters-changed', () => {
+      // The workspace is scanned again with the new filters
+      store.set(pdfAtom, []);
+      store.set(loadedDirsAtom, {});
+    });
+
+    await listen<Entries[]>('loose-files-updated', (event) => {
+      store.set(looseFilesAtom, event.payload);
+    });
+
+    await listen<{ message: string; backup: string | null }>('state-load-failed', (event) => {
+      const { message: reason, backup } = event.payload;
+      const details = backup == null ? reason : `${reason}\n\nThe unreadable file was moved to ${backup}`;
+
+      void message(details, { title: 'Could not load saved workspaces', kind: 'error' });
+    });
+
     // After all events have been added, let the backend know we are fully ready
     void invoke('frontend_ready');
   } catch (error) {